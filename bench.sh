@@ -0,0 +1,3 @@
+cargo run -- test test/bench/page.tsx test/bench/hoisted.js
+cargo run -- test --no-hoist test/bench/page.tsx test/bench/inline.js
+node --expose-gc test/bench/bench.js | tee bench_output.txt
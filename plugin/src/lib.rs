@@ -0,0 +1,91 @@
+// The transpiler as an swc Wasm plugin, for `.swcrc` based toolchains.
+// Built with `cargo build --release -p framework-js-swc-plugin --target wasm32-wasip1`
+
+use framework_js::{TranspileVisitor, VisitorOptions};
+use swc_core::{
+  common::errors::HANDLER,
+  ecma::{
+    ast::Program,
+    visit::{as_folder, FoldWith},
+  },
+  plugin::{plugin_transform, proxies::TransformPluginProgramMetadata},
+};
+
+// The plugin's options in `.swcrc`, the same as the config file's:
+// `"plugins": [["framework-js-swc-plugin", { "atomicStyles": true }]]`
+pub type PluginOptions = VisitorOptions;
+
+#[plugin_transform]
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+  let options = parse_options(metadata.get_transform_plugin_config());
+  program.fold_with(&mut as_folder(TranspileVisitor::with_options(options)))
+}
+
+// Invalid options are reported to swc, which fails the build, and the
+// defaults are used until then
+fn parse_options(config: Option<String>) -> PluginOptions {
+  let Some(config) = config else {
+    return PluginOptions::default();
+  };
+  serde_json::from_str::<PluginOptions>(&config).unwrap_or_else(|error| {
+    HANDLER.with(|handler| {
+      handler
+        .struct_err(&format!(
+          "invalid options for framework-js-swc-plugin: {error}"
+        ))
+        .emit()
+    });
+    PluginOptions::default()
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::{parse_options, PluginOptions};
+  use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+  };
+  use swc_core::common::errors::{Handler, HANDLER};
+
+  #[derive(Clone, Default)]
+  struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for Diagnostics {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn options_are_camel_case_with_defaults() {
+    let options = serde_json::from_str::<PluginOptions>(r#"{ "atomicStyles": true }"#).unwrap();
+    assert!(options.hoist_static_templates);
+    assert!(options.atomic_styles);
+
+    assert!(serde_json::from_str::<PluginOptions>(r#"{ "atomic_styles": true }"#).is_err());
+  }
+
+  #[test]
+  fn invalid_options_are_reported() {
+    let diagnostics = Diagnostics::default();
+    let handler = Handler::with_emitter_writer(Box::new(diagnostics.clone()), None);
+
+    let options = HANDLER.set(&handler, || {
+      parse_options(Some(r#"{ "atomicStyles": "yes" }"#.to_owned()))
+    });
+    assert!(!options.atomic_styles);
+    assert!(handler.has_errors());
+    let diagnostics = String::from_utf8(diagnostics.0.lock().unwrap().clone()).unwrap();
+    assert!(
+      diagnostics.contains("invalid options for framework-js-swc-plugin"),
+      "{diagnostics}"
+    );
+
+    assert!(parse_options(None).hoist_static_templates);
+  }
+}
@@ -0,0 +1,327 @@
+use crate::commands::{
+  cache::{self, Cache},
+  flags::{CompileFlags, Settings},
+  path,
+};
+use clap::Args;
+use framework_js::{ComponentInfo, Output};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+  process,
+  sync::Arc,
+  time::Instant,
+};
+use swc_common::SourceMap;
+
+// What gets compiled, `.d.ts` files aside
+pub const EXTENSIONS: [&str; 5] = ["ts", "tsx", "js", "jsx", "mjs"];
+
+#[derive(Debug, Args)]
+pub struct BuildCommandInfo {
+  /// Every source file under it is compiled
+  input: PathBuf,
+  /// Where the output goes, in the same layout
+  output: PathBuf,
+
+  /// How many files are compiled at once, every core by default
+  #[arg(short, long)]
+  jobs: Option<usize>,
+
+  /// Where compiled files are kept between builds
+  #[arg(long, default_value = "node_modules/.cache/framework-js")]
+  cache_dir: PathBuf,
+
+  /// Compile every file, even the unchanged ones
+  #[arg(long)]
+  no_cache: bool,
+
+  /// Write every component found, and what it needs at runtime, to this JSON file
+  #[arg(long)]
+  manifest: Option<PathBuf>,
+
+  #[command(flatten)]
+  flags: CompileFlags,
+}
+
+pub fn build(info: BuildCommandInfo) {
+  let input_dir = path::make_abs_path(info.input).unwrap();
+  let output_dir = path::make_abs_path(info.output).unwrap();
+
+  let settings = match info.flags.load() {
+    Ok(settings) => settings,
+    Err(error) => {
+      eprintln!("{error:#}");
+      process::exit(1);
+    }
+  };
+
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(info.jobs.unwrap_or(0))
+    .build()
+    .unwrap();
+
+  let cache = match info.no_cache {
+    true => None,
+    false => Some(Cache::new(path::make_abs_path(info.cache_dir).unwrap())),
+  };
+
+  let start = Instant::now();
+  let built = pool.install(|| build_dir(&input_dir, &output_dir, &settings, cache.as_ref()));
+  if let Some(cache) = &cache {
+    // Only a bigger cache if it fails
+    let _ = cache.prune(cache::MAX_ENTRIES);
+  }
+  let BuiltFiles {
+    total,
+    cached,
+    mut components,
+    errors,
+  } = built.expect("failed to read the input directory");
+
+  if let Some(manifest) = info.manifest {
+    // Stable between builds, whatever order the files were compiled in
+    components.sort_by(|a, b| a.file.cmp(&b.file));
+    let json = serde_json::to_string_pretty(&Manifest { components }).unwrap();
+    fs::write(path::make_abs_path(manifest).unwrap(), json).expect("failed to write the manifest");
+  }
+
+  for error in &errors {
+    eprintln!("{error}");
+  }
+  eprintln!(
+    "Compiled {} of {total} files in {:.2?}, {cached} unchanged",
+    total - errors.len(),
+    start.elapsed()
+  );
+  if !errors.is_empty() {
+    process::exit(1);
+  }
+}
+
+// Past this many files a build starts a new `SourceMap`, it keeps the
+// source of every file added to it until it's dropped
+const FILES_PER_SOURCE_MAP: usize = 256;
+
+// What a build of a whole directory did
+struct BuiltFiles {
+  total: usize,
+  cached: usize,
+  components: Vec<ManifestEntry>,
+  // Sorted, one per file that failed
+  errors: Vec<String>,
+}
+
+// Compiles every file under `input_dir` on the current thread pool
+fn build_dir(
+  input_dir: &Path,
+  output_dir: &Path,
+  settings: &Settings,
+  cache: Option<&Cache>,
+) -> io::Result<BuiltFiles> {
+  let mut files = vec![];
+  collect_files(input_dir, Some(output_dir), &mut files)?;
+
+  let mut built = BuiltFiles {
+    total: files.len(),
+    cached: 0,
+    components: vec![],
+    errors: vec![],
+  };
+  for batch in files.chunks(FILES_PER_SOURCE_MAP) {
+    // Shared, so positions in diagnostics are unique across the batch
+    let cm = Arc::<SourceMap>::default();
+    let results = batch
+      .par_iter()
+      .map(|file| {
+        let output_file = output_path(file, input_dir, output_dir);
+        build_file(cm.clone(), settings, cache, file, &output_file)
+      })
+      .collect::<Vec<_>>();
+
+    for (file, result) in batch.iter().zip(results) {
+      match result {
+        Ok((from_cache, found)) => {
+          built.cached += from_cache as usize;
+          let file = file.strip_prefix(input_dir).unwrap();
+          let file = file.to_string_lossy().replace('\\', "/");
+          built
+            .components
+            .extend(found.into_iter().map(|component| ManifestEntry {
+              file: file.clone(),
+              component,
+            }));
+        }
+        Err(error) => built.errors.push(error),
+      }
+    }
+  }
+  built.errors.sort();
+
+  Ok(built)
+}
+
+#[derive(Serialize)]
+struct Manifest {
+  components: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+  // Relative to the input directory
+  file: String,
+  #[serde(flatten)]
+  component: ComponentInfo,
+}
+
+// Whether it came from the cache, and the components in it
+fn build_file(
+  cm: Arc<SourceMap>,
+  settings: &Settings,
+  cache: Option<&Cache>,
+  file: &Path,
+  output_file: &Path,
+) -> Result<(bool, Vec<ComponentInfo>), String> {
+  let source = fs::read_to_string(file).map_err(|e| format!("{}: {e}", file.display()))?;
+  let options = settings
+    .options(file.to_owned())
+    .map_err(|e| format!("{}: {e:#}", file.display()))?;
+
+  let key = cache.map(|cache| cache.key(&source, &options));
+  let cached = cache
+    .zip(key.as_deref())
+    .and_then(|(cache, key)| cache.get(key, &options));
+  let (output, from_cache) = match cached {
+    Some(output) => (output, true),
+    None => {
+      let output = framework_js::transpile_with_source_map(cm, source, &options)
+        .map_err(|diagnostics| diagnostics.to_string())?;
+      if let Some((cache, key)) = cache.zip(key.as_deref()) {
+        // Only a slower next build if it fails
+        let _ = cache.put(key, &output);
+      }
+      (output, false)
+    }
+  };
+
+  let components = output.components.clone();
+  write_output(output_file, output).map_err(|e| format!("{}: {e}", output_file.display()))?;
+  Ok((from_cache, components))
+}
+
+// The code, and the source map next to it if there's one
+pub fn write_output(output_file: &Path, output: Output) -> io::Result<()> {
+  if let Some(dir) = output_file.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  let mut code = output.code;
+  if let Some(map) = output.map {
+    let mut map_file = output_file.as_os_str().to_owned();
+    map_file.push(".map");
+    fs::write(&map_file, map)?;
+
+    let map_name = Path::new(&map_file).file_name().unwrap().to_string_lossy();
+    code.push_str(&format!("\n//# sourceMappingURL={map_name}\n"));
+  }
+
+  fs::write(output_file, code)
+}
+
+// Every source file under `dir`, skipping the output if it's in there
+pub fn collect_files(
+  dir: &Path,
+  output_dir: Option<&Path>,
+  files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    if path.is_dir() {
+      if name.starts_with('.') || name == "node_modules" || Some(path.as_path()) == output_dir {
+        continue;
+      }
+      collect_files(&path, output_dir, files)?;
+    } else if !name.ends_with(".d.ts")
+      && path
+        .extension()
+        .is_some_and(|extension| EXTENSIONS.iter().any(|e| extension == *e))
+    {
+      files.push(path);
+    }
+  }
+
+  Ok(())
+}
+
+fn output_path(file: &Path, input_dir: &Path, output_dir: &Path) -> PathBuf {
+  let output_file = output_dir.join(file.strip_prefix(input_dir).unwrap());
+  match file.extension().is_some_and(|extension| extension == "mjs") {
+    true => output_file,
+    false => output_file.with_extension("js"),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::build_dir;
+  use crate::commands::flags::CompileFlags;
+  use clap::Parser;
+  use std::fs;
+
+  #[derive(Parser)]
+  struct Cli {
+    #[command(flatten)]
+    flags: CompileFlags,
+  }
+
+  #[test]
+  fn directories_are_built_into_the_output() {
+    let root = std::env::temp_dir().join(format!("framework-js-build-{}", std::process::id()));
+    let src = root.join("src");
+    fs::create_dir_all(src.join("cards")).unwrap();
+    fs::create_dir_all(src.join("node_modules/lib")).unwrap();
+    fs::write(
+      src.join("page.tsx"),
+      "export default function Page() { return <p>Page</p>; }",
+    )
+    .unwrap();
+    fs::write(
+      src.join("cards/card.mjs"),
+      "export default function Card() { return <p>Card</p>; }",
+    )
+    .unwrap();
+    fs::write(src.join("types.d.ts"), "export type Id = string;").unwrap();
+    fs::write(src.join("node_modules/lib/index.js"), "export {};").unwrap();
+    fs::write(src.join("broken.tsx"), "export default () => <p>;").unwrap();
+
+    let settings = Cli::parse_from(["framework-js"]).flags.load().unwrap();
+    // The output is inside the input, and never compiled again
+    let dist = src.join("dist");
+    for _ in 0..2 {
+      let built = build_dir(&src, &dist, &settings, None).unwrap();
+      assert_eq!(built.total, 3);
+      assert_eq!(built.errors.len(), 1);
+      assert!(built.errors[0].contains("broken.tsx"), "{:?}", built.errors);
+
+      let mut files = built
+        .components
+        .iter()
+        .map(|entry| entry.file.as_str())
+        .collect::<Vec<_>>();
+      files.sort();
+      assert_eq!(files, ["cards/card.mjs", "page.tsx"]);
+    }
+
+    let page = fs::read_to_string(dist.join("page.js")).unwrap();
+    assert!(page.contains("`<p>Page</p>`"), "{page}");
+    assert!(dist.join("cards/card.mjs").is_file());
+    assert!(!dist.join("types.d.js").exists());
+    assert!(!dist.join("broken.js").exists());
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
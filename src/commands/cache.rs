@@ -0,0 +1,180 @@
+use framework_js::{Options, Output};
+use sha2::{Digest, Sha256};
+use std::{
+  cmp::Reverse,
+  env,
+  fs::{self, File},
+  io,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+// The most entries kept, the least recently used ones go first
+pub const MAX_ENTRIES: usize = 10_000;
+
+// Compiled files by a hash of everything that went into them, so unchanged
+// files skip parsing and the transform entirely
+pub struct Cache {
+  dir: PathBuf,
+  compiler: String,
+}
+
+impl Cache {
+  pub fn new(dir: PathBuf) -> Cache {
+    Cache {
+      dir,
+      compiler: compiler_version(),
+    }
+  }
+
+  pub fn key(&self, source: &str, options: &Options) -> String {
+    // Their JSON rather than `Hash`, whose bytes can change between Rust releases.
+    // It ends where its brackets do, so it can't run into the source
+    let options = serde_json::to_vec(&(&self.compiler, options)).unwrap();
+    let hash = Sha256::new()
+      .chain_update(options)
+      .chain_update(source)
+      .finalize();
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+  }
+
+  // The code, source map and components of an entry, if its imports still
+  // resolve to the same files
+  pub fn get(&self, key: &str, options: &Options) -> Option<Output> {
+    let path = self.dir.join(format!("{key}.json"));
+    let json = fs::read_to_string(&path).ok()?;
+    let output = serde_json::from_str::<Output>(&json).ok()?;
+
+    let resolve = |specifier: &str| {
+      let aliases = options.aliases.as_ref()?;
+      aliases.resolve(specifier, &options.filename)
+    };
+    let unchanged = output
+      .resolved_imports
+      .iter()
+      .all(|(specifier, resolved)| resolve(specifier) == *resolved);
+    if !unchanged {
+      return None;
+    }
+
+    // Used, so it's the last to be pruned
+    let _ = File::options()
+      .append(true)
+      .open(&path)
+      .and_then(|file| file.set_modified(SystemTime::now()));
+    Some(output)
+  }
+
+  pub fn put(&self, key: &str, output: &Output) -> io::Result<()> {
+    fs::create_dir_all(&self.dir)?;
+
+    // Renamed into place, so parallel or interrupted builds never read half an entry
+    let path = self.dir.join(format!("{key}.json"));
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temp, serde_json::to_string(output)?)?;
+    fs::rename(temp, path)
+  }
+
+  // Removes all but the `max` most recently used entries
+  pub fn prune(&self, max: usize) -> io::Result<()> {
+    let mut entries = fs::read_dir(&self.dir)?
+      .filter_map(|entry| {
+        let entry = entry.ok()?;
+        let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+        Some((modified, entry.path()))
+      })
+      .collect::<Vec<_>>();
+    if entries.len() <= max {
+      return Ok(());
+    }
+
+    entries.sort_by_key(|(modified, _)| Reverse(*modified));
+    for (_, path) in &entries[max..] {
+      fs::remove_file(path)?;
+    }
+    Ok(())
+  }
+}
+
+// The version alone misses changes to unreleased compilers, so the
+// executable's size and modification time are part of it too
+fn compiler_version() -> String {
+  let executable = env::current_exe()
+    .and_then(fs::metadata)
+    .ok()
+    .map(|metadata| {
+      let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+      format!("{}-{}", metadata.len(), modified.as_nanos())
+    })
+    .unwrap_or_default();
+  format!("{}+{executable}", env!("CARGO_PKG_VERSION"))
+}
+
+#[cfg(test)]
+mod test {
+  use super::Cache;
+  use framework_js::{Options, PathAliases};
+  use std::fs;
+
+  #[test]
+  fn entries_are_keyed_by_source_and_options() {
+    let dir = std::env::temp_dir().join(format!("framework-js-cache-{}", std::process::id()));
+    let cache = Cache::new(dir.clone());
+
+    let options = Options::default();
+    let key = cache.key("<p />", &options);
+    assert_eq!(key, cache.key("<p />", &options));
+    assert_eq!(key.len(), 64);
+    assert_ne!(key, cache.key("<b />", &options));
+    let minified = Options {
+      minify: true,
+      ..Options::default()
+    };
+    assert_ne!(key, cache.key("<p />", &minified));
+
+    assert!(cache.get(&key, &options).is_none());
+    let output = framework_js::transpile("export default () => <p />;", &options).unwrap();
+    cache.put(&key, &output).unwrap();
+    let cached = cache.get(&key, &options).unwrap();
+    assert_eq!(cached.code, output.code);
+    assert_eq!(cached.components, output.components);
+
+    cache.put(&cache.key("<b />", &options), &output).unwrap();
+    cache.prune(1).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn entries_with_aliases_that_resolve_elsewhere_are_stale() {
+    let root =
+      std::env::temp_dir().join(format!("framework-js-cache-aliases-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    let cache = Cache::new(root.join("cache"));
+
+    let options = Options {
+      filename: root.join("src/page.tsx"),
+      aliases: Some(PathAliases::new(
+        Some(root.clone()),
+        [("@/*".to_owned(), vec!["src/*".to_owned()])],
+      )),
+      ..Options::default()
+    };
+    let source = "import Card from '@/Card'; export default () => <Card />;";
+    let key = cache.key(source, &options);
+    let output = framework_js::transpile(source, &options).unwrap();
+    cache.put(&key, &output).unwrap();
+    assert!(cache.get(&key, &options).is_some());
+
+    // Same source and options, but `@/Card` is a `.tsx` now
+    fs::write(root.join("src/Card.tsx"), "").unwrap();
+    assert!(cache.get(&key, &options).is_none());
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
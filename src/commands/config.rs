@@ -0,0 +1,118 @@
+use anyhow::Context;
+use framework_js::{ModuleKind, Options, VisitorOptions};
+use serde::Deserialize;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+use swc_ecma_ast::EsVersion;
+
+pub const CONFIG_FILE_NAME: &str = "framework.config.json";
+
+// `framework.config.json`, or the `"framework"` field of `package.json`.
+// Everything left out keeps its default, and CLI flags win over all of it
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Config {
+  pub target: Option<EsVersion>,
+  pub typescript: Option<bool>,
+  pub jsx: Option<bool>,
+  pub module: Option<ModuleKind>,
+  pub minify: Option<bool>,
+  // The same options the swc plugin takes
+  pub transform: Option<VisitorOptions>,
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+  framework: Option<Config>,
+}
+
+impl Config {
+  pub fn load(path: &Path) -> anyhow::Result<Config> {
+    let json =
+      fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("invalid config in {}", path.display()))
+  }
+
+  // The closest config to `dir`, `framework.config.json` before `package.json`
+  pub fn discover(dir: &Path) -> anyhow::Result<Option<(PathBuf, Config)>> {
+    for dir in dir.ancestors() {
+      let path = dir.join(CONFIG_FILE_NAME);
+      if path.is_file() {
+        let config = Config::load(&path)?;
+        return Ok(Some((path, config)));
+      }
+
+      let path = dir.join("package.json");
+      if path.is_file() {
+        let json = fs::read_to_string(&path)?;
+        let package = serde_json::from_str::<PackageJson>(&json)
+          .with_context(|| format!("invalid config in {}", path.display()))?;
+        if let Some(config) = package.framework {
+          return Ok(Some((path, config)));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  pub fn options(&self, defaults: Options) -> Options {
+    Options {
+      target: self.target.unwrap_or(defaults.target),
+      typescript: self.typescript.unwrap_or(defaults.typescript),
+      jsx: self.jsx.unwrap_or(defaults.jsx),
+      module: self.module.unwrap_or(defaults.module),
+      minify: self.minify.unwrap_or(defaults.minify),
+      visitor: self.transform.clone().unwrap_or(defaults.visitor),
+      ..defaults
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::Config;
+  use framework_js::{Options, Streaming};
+  use std::fs;
+  use swc_ecma_ast::EsVersion;
+
+  #[test]
+  fn closest_config_is_discovered() {
+    let root = std::env::temp_dir().join(format!("framework-js-config-{}", std::process::id()));
+    let nested = root.join("src/components");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(
+      root.join("package.json"),
+      r#"{ "name": "app", "framework": { "target": "es2019", "transform": { "streaming": "buffered" } } }"#,
+    )
+    .unwrap();
+
+    let (path, config) = Config::discover(&nested).unwrap().unwrap();
+    assert_eq!(path, root.join("package.json"));
+    let options = config.options(Options::default());
+    assert_eq!(options.target, EsVersion::Es2019);
+    assert!(options.typescript);
+    assert_eq!(options.visitor.streaming, Streaming::Buffered);
+    assert!(options.visitor.hoist_static_templates);
+
+    fs::write(
+      root.join("src/framework.config.json"),
+      r#"{ "minify": true }"#,
+    )
+    .unwrap();
+    let (path, config) = Config::discover(&nested).unwrap().unwrap();
+    assert_eq!(path, root.join("src/framework.config.json"));
+    assert!(config.options(Options::default()).minify);
+
+    fs::write(
+      root.join("src/framework.config.json"),
+      r#"{ "minfy": true }"#,
+    )
+    .unwrap();
+    assert!(Config::discover(&nested).is_err());
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
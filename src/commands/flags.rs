@@ -0,0 +1,218 @@
+use crate::commands::{
+  config::Config,
+  tsconfig::{self, TsConfig},
+};
+use clap::Args;
+use framework_js::{ModuleKind, Options, Streaming};
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+use swc_ecma_ast::EsVersion;
+
+// The flags of every command that compiles
+#[derive(Debug, Args)]
+pub struct CompileFlags {
+  /// Use this config, instead of the closest `framework.config.json`
+  /// or `package.json` `"framework"` field
+  #[arg(short, long)]
+  config: Option<PathBuf>,
+
+  /// Use this tsconfig, instead of the closest `tsconfig.json`
+  #[arg(short, long)]
+  project: Option<PathBuf>,
+
+  /// The newest JS the output may use: es5, es2017, es2020, esnext...
+  #[arg(short, long, value_parser = tsconfig::parse_target)]
+  target: Option<EsVersion>,
+
+  /// The module system of the output
+  #[arg(long, value_enum)]
+  module: Option<ModuleKind>,
+
+  #[arg(short, long, overrides_with = "no_minify")]
+  minify: bool,
+
+  /// Don't minify, even if the config does
+  #[arg(long, overrides_with = "minify")]
+  no_minify: bool,
+
+  /// Write a `.map` next to every output file
+  #[arg(long)]
+  source_map: bool,
+
+  /// Move long static html into module level constants
+  #[arg(long, overrides_with = "no_hoist")]
+  hoist: bool,
+
+  /// Keep long static html inline, instead of in module level constants
+  #[arg(long, overrides_with = "hoist")]
+  no_hoist: bool,
+
+  /// Turn static `style={{...}}` objects into atomic classes in one stylesheet
+  #[arg(long, overrides_with = "no_atomic_styles")]
+  atomic_styles: bool,
+
+  /// Keep static styles inline, even if the config turns atomic styles on
+  #[arg(long, overrides_with = "atomic_styles")]
+  no_atomic_styles: bool,
+
+  /// How `async function*` components are sent
+  #[arg(long, value_enum)]
+  streaming: Option<Streaming>,
+}
+
+// The tsconfig and our config that apply to a directory
+type Configs = (Arc<TsConfig>, Arc<Config>);
+
+// Everything a file's `Options` come from, loaded once per command
+pub struct Settings {
+  // `--project`, otherwise the closest one to each file
+  tsconfig: Option<Arc<TsConfig>>,
+  // `--config`, otherwise the closest one to each file
+  config: Option<Arc<Config>>,
+  // Directory => the closest configs to it, so they're only looked for once
+  discovered: Mutex<HashMap<PathBuf, Configs>>,
+  flags: CompileFlags,
+}
+
+impl CompileFlags {
+  pub fn load(self) -> anyhow::Result<Settings> {
+    let tsconfig = match &self.project {
+      Some(path) => Some(Arc::new(TsConfig::load(path)?)),
+      None => None,
+    };
+    let config = match &self.config {
+      Some(path) => Some(Arc::new(Config::load(path)?)),
+      None => None,
+    };
+
+    Ok(Settings {
+      tsconfig,
+      config,
+      discovered: Mutex::default(),
+      flags: self,
+    })
+  }
+}
+
+// `--x` and `--no-x`, whichever came last, `None` if neither did
+fn toggle(on: bool, off: bool) -> Option<bool> {
+  match (on, off) {
+    (true, _) => Some(true),
+    (_, true) => Some(false),
+    _ => None,
+  }
+}
+
+impl Settings {
+  // Defaults, then the tsconfig, then our config, then the flags
+  pub fn options(&self, filename: PathBuf) -> anyhow::Result<Options> {
+    let (tsconfig, config) = self.configs_for(&filename)?;
+
+    let mut options = Options {
+      filename,
+      ..Options::default()
+    };
+    tsconfig.apply(&mut options);
+
+    let flags = &self.flags;
+    let mut options = config.options(options);
+    if let Some(target) = flags.target {
+      options.target = target;
+    }
+    if let Some(module) = flags.module {
+      options.module = module;
+    }
+    if let Some(minify) = toggle(flags.minify, flags.no_minify) {
+      options.minify = minify;
+    }
+    options.source_map |= flags.source_map;
+    if let Some(hoist) = toggle(flags.hoist, flags.no_hoist) {
+      options.visitor.hoist_static_templates = hoist;
+    }
+    if let Some(atomic_styles) = toggle(flags.atomic_styles, flags.no_atomic_styles) {
+      options.visitor.atomic_styles = atomic_styles;
+    }
+    if let Some(streaming) = flags.streaming {
+      options.visitor.streaming = streaming;
+    }
+
+    Ok(options)
+  }
+
+  // The closest configs to the file, not to wherever the command was run from
+  fn configs_for(&self, file: &Path) -> anyhow::Result<Configs> {
+    let dir = file.parent().unwrap_or(file).to_owned();
+    if let Some(configs) = self.discovered.lock().unwrap().get(&dir) {
+      return Ok(configs.clone());
+    }
+
+    let tsconfig = match &self.tsconfig {
+      Some(tsconfig) => tsconfig.clone(),
+      None => Arc::new(TsConfig::discover(&dir)?.unwrap_or_default()),
+    };
+    let config = match &self.config {
+      Some(config) => config.clone(),
+      None => Arc::new(Config::discover(&dir)?.map_or_else(Config::default, |(_, config)| config)),
+    };
+    let configs = (tsconfig, config);
+    self.discovered.lock().unwrap().insert(dir, configs.clone());
+    Ok(configs)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::CompileFlags;
+  use clap::Parser;
+  use std::fs;
+  use swc_ecma_ast::EsVersion;
+
+  #[derive(Parser)]
+  struct Cli {
+    #[command(flatten)]
+    flags: CompileFlags,
+  }
+
+  #[test]
+  fn flags_override_the_closest_config() {
+    let root = std::env::temp_dir().join(format!("framework-js-flags-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+      root.join("framework.config.json"),
+      r#"{ "minify": true, "transform": { "atomicStyles": true } }"#,
+    )
+    .unwrap();
+    fs::write(
+      root.join("tsconfig.json"),
+      r#"{ "compilerOptions": { "target": "es2019" } }"#,
+    )
+    .unwrap();
+    let file = root.join("src/page.tsx");
+
+    let options = |args: &[&str]| {
+      let args = std::iter::once("framework-js").chain(args.iter().copied());
+      let settings = Cli::parse_from(args).flags.load().unwrap();
+      let options = settings.options(file.clone()).unwrap();
+      (options.minify, options.visitor.atomic_styles)
+    };
+
+    // Found next to the file, not in the directory the command runs in
+    assert_eq!(options(&[]), (true, true));
+    let settings = Cli::parse_from(["framework-js"]).flags.load().unwrap();
+    assert_eq!(
+      settings.options(file.clone()).unwrap().target,
+      EsVersion::Es2019
+    );
+    assert_eq!(
+      options(&["--no-minify", "--no-atomic-styles"]),
+      (false, false)
+    );
+    assert_eq!(options(&["--no-minify", "--minify"]), (true, true));
+    assert_eq!(options(&["--minify", "--no-minify"]), (false, true));
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
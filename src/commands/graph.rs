@@ -0,0 +1,525 @@
+use crate::commands::{
+  build::{collect_files, EXTENSIONS},
+  flags::CompileFlags,
+  path,
+};
+use clap::{Args, ValueEnum};
+use framework_js::{manifest::Boundary, ComponentInfo, Options, Render};
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Component, Path, PathBuf},
+  process,
+};
+use swc_common::{FileName, SourceMap};
+use swc_ecma_ast::{EsVersion, ImportSpecifier, ModuleDecl, ModuleExportName, ModuleItem};
+use swc_ecma_parser::{EsConfig, Syntax, TsConfig};
+
+#[derive(Debug, Args)]
+pub struct GraphCommandInfo {
+  /// A component file, or a directory of them
+  input: PathBuf,
+
+  #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+  format: GraphFormat,
+
+  #[command(flatten)]
+  flags: CompileFlags,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+  Dot,
+  Mermaid,
+}
+
+// A file, with what it renders and where the names it renders come from
+struct Module {
+  file: PathBuf,
+  // Relative to the input, `/` separated
+  name: String,
+  components: Vec<ComponentInfo>,
+  renders: Vec<Render>,
+  // Local name => (the relative import or package, the export or `None` for `* as`)
+  imports: HashMap<String, (String, Option<String>)>,
+}
+
+// A component of a module, or the module itself for JSX outside of every component
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+  module: String,
+  name: Option<String>,
+}
+
+impl Node {
+  // Unique across the whole graph, unlike the name
+  fn id(&self) -> String {
+    match &self.name {
+      Some(name) => format!("{}#{name}", self.module),
+      None => self.module.clone(),
+    }
+  }
+
+  fn label(&self) -> &str {
+    self.name.as_deref().unwrap_or(&self.module)
+  }
+}
+
+#[derive(Debug, PartialEq)]
+struct Edge {
+  from: Node,
+  to: Node,
+  boundary: Boundary,
+  member: bool,
+}
+
+pub fn graph(info: GraphCommandInfo) {
+  let input = path::make_abs_path(info.input).unwrap();
+
+  let settings = match info.flags.load() {
+    Ok(settings) => settings,
+    Err(error) => {
+      eprintln!("{error:#}");
+      process::exit(1);
+    }
+  };
+
+  let mut files = vec![];
+  match input.is_dir() {
+    true => collect_files(&input, None, &mut files).expect("failed to read the input directory"),
+    false => files.push(input.clone()),
+  }
+  files.sort();
+
+  let mut modules = vec![];
+  for file in files {
+    let source = fs::read_to_string(&file).expect("failed to load file");
+    let options = match settings.options(file.clone()) {
+      Ok(options) => options,
+      Err(error) => {
+        eprintln!("{error:#}");
+        process::exit(1);
+      }
+    };
+
+    let name = match file.strip_prefix(&input) {
+      Ok(relative) if !relative.as_os_str().is_empty() => relative,
+      _ => file.file_name().unwrap().as_ref(),
+    };
+    let name = name.to_string_lossy().replace('\\', "/");
+    match compile(file.clone(), name, source, &options) {
+      Ok(module) => modules.push(module),
+      Err(diagnostics) => {
+        eprintln!("{diagnostics}");
+        process::exit(1);
+      }
+    }
+  }
+
+  print!("{}", render_graph(&edges(&modules), info.format));
+}
+
+fn compile(
+  file: PathBuf,
+  name: String,
+  source: String,
+  options: &Options,
+) -> Result<Module, String> {
+  let imports = imports(&file, &source, options);
+  let output = framework_js::transpile(source, options).map_err(|error| error.to_string())?;
+  Ok(Module {
+    file,
+    name,
+    components: output.components,
+    renders: output.renders,
+    imports,
+  })
+}
+
+// The value imports of a module, aliases resolved like the compiler does
+fn imports(
+  file: &Path,
+  source: &str,
+  options: &Options,
+) -> HashMap<String, (String, Option<String>)> {
+  let cm = SourceMap::default();
+  let fm = cm.new_source_file(FileName::Real(file.to_owned()), source.to_owned());
+  let syntax = match options.typescript {
+    true => Syntax::Typescript(TsConfig {
+      tsx: options.jsx,
+      ..Default::default()
+    }),
+    false => Syntax::Es(EsConfig {
+      jsx: options.jsx,
+      ..Default::default()
+    }),
+  };
+  // Already compiled, so it parses
+  let Ok(module) =
+    swc_ecma_parser::parse_file_as_module(&fm, syntax, EsVersion::EsNext, None, &mut vec![])
+  else {
+    return HashMap::new();
+  };
+
+  let mut imports = HashMap::new();
+  for item in module.body {
+    let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+      continue;
+    };
+    if import.type_only {
+      continue;
+    }
+
+    let specifier = import.src.value.to_string();
+    let specifier = options
+      .aliases
+      .as_ref()
+      .and_then(|aliases| aliases.resolve(&specifier, file))
+      .unwrap_or(specifier);
+    for imported in import.specifiers {
+      let (local, export) = match imported {
+        ImportSpecifier::Default(default) => (default.local, Some("default".to_owned())),
+        ImportSpecifier::Namespace(namespace) => (namespace.local, None),
+        ImportSpecifier::Named(named) if !named.is_type_only => {
+          let export = match named.imported {
+            Some(ModuleExportName::Ident(ident)) => ident.sym.to_string(),
+            Some(ModuleExportName::Str(str)) => str.value.to_string(),
+            None => named.local.sym.to_string(),
+          };
+          (named.local, Some(export))
+        }
+        ImportSpecifier::Named(_) => continue,
+      };
+      imports.insert(local.sym.to_string(), (specifier.clone(), export));
+    }
+  }
+  imports
+}
+
+// Once each, in the order they're rendered, with imported names followed
+// to the module that defines them
+fn edges(modules: &[Module]) -> Vec<Edge> {
+  let mut edges = vec![];
+  for module in modules {
+    for render in &module.renders {
+      let edge = Edge {
+        from: Node {
+          module: module.name.clone(),
+          name: render.from.clone(),
+        },
+        to: render_target(modules, module, &render.to),
+        boundary: render.boundary,
+        member: render.member,
+      };
+      if !edges.contains(&edge) {
+        edges.push(edge);
+      }
+    }
+  }
+  edges
+}
+
+// `Card`, or `UI.Card`, as rendered in `module`
+fn render_target(modules: &[Module], module: &Module, to: &str) -> Node {
+  let (local, rest) = match to.split_once('.') {
+    Some((local, rest)) => (local, Some(rest)),
+    None => (to, None),
+  };
+  let Some((specifier, export)) = module.imports.get(local) else {
+    return Node {
+      module: module.name.clone(),
+      name: Some(to.to_owned()),
+    };
+  };
+
+  // `* as UI` is the module, `UI.Card` its `Card` export
+  let (export, rest) = match (export, rest) {
+    (Some(export), rest) => (export.as_str(), rest),
+    (None, Some(rest)) => match rest.split_once('.') {
+      Some((export, rest)) => (export, Some(rest)),
+      None => (rest, None),
+    },
+    (None, None) => ("*", None),
+  };
+
+  let Some(target) = imported_module(modules, module, specifier) else {
+    // A package, or a file that isn't part of the graph
+    let name = rest.map_or_else(|| export.to_owned(), |rest| format!("{export}.{rest}"));
+    return Node {
+      module: specifier.clone(),
+      name: Some(name),
+    };
+  };
+  if export == "*" {
+    return Node {
+      module: target.name.clone(),
+      name: None,
+    };
+  }
+
+  // Named like it is where it's defined, anonymous defaults stay `default`
+  let name = target
+    .components
+    .iter()
+    .find(|component| component.export.as_deref() == Some(export))
+    .map_or(export, |component| component.name.as_str());
+  Node {
+    module: target.name.clone(),
+    name: Some(rest.map_or_else(|| name.to_owned(), |rest| format!("{name}.{rest}"))),
+  }
+}
+
+// The module a relative import points to, with or without its extension
+fn imported_module<'a>(
+  modules: &'a [Module],
+  importer: &Module,
+  specifier: &str,
+) -> Option<&'a Module> {
+  if !specifier.starts_with('.') {
+    return None;
+  }
+
+  let mut path = PathBuf::new();
+  for component in importer.file.parent()?.join(specifier).components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        path.pop();
+      }
+      component => path.push(component),
+    }
+  }
+  // `./card.js` is how ES modules import `card.tsx`
+  if path
+    .extension()
+    .is_some_and(|extension| EXTENSIONS.iter().any(|e| extension == *e))
+  {
+    path.set_extension("");
+  }
+
+  let index = path.join("index");
+  modules.iter().find(|module| {
+    let file = module.file.with_extension("");
+    file == path || file == index
+  })
+}
+
+// Async edges are the ones that become a `<div id>` placeholder
+fn label(edge: &Edge) -> Option<String> {
+  let boundary = match edge.boundary {
+    Boundary::Sync => None,
+    Boundary::Async => Some("async"),
+    Boundary::Stream => Some("stream"),
+  };
+  let member = edge.member.then_some("member");
+
+  let parts = boundary.into_iter().chain(member).collect::<Vec<_>>();
+  match parts.is_empty() {
+    true => None,
+    false => Some(parts.join(", ")),
+  }
+}
+
+// Every node once, grouped by module, both in the order they're first used
+fn modules_of(edges: &[Edge]) -> Vec<(&str, Vec<&Node>)> {
+  let mut modules: Vec<(&str, Vec<&Node>)> = vec![];
+  for node in edges.iter().flat_map(|edge| [&edge.from, &edge.to]) {
+    let index = match modules
+      .iter()
+      .position(|(module, _)| *module == node.module)
+    {
+      Some(index) => index,
+      None => {
+        modules.push((&node.module, vec![]));
+        modules.len() - 1
+      }
+    };
+    if !modules[index].1.contains(&node) {
+      modules[index].1.push(node);
+    }
+  }
+  modules
+}
+
+fn render_graph(edges: &[Edge], format: GraphFormat) -> String {
+  let mut graph = String::new();
+  match format {
+    GraphFormat::Dot => {
+      graph.push_str("digraph components {\n");
+      for (index, (module, nodes)) in modules_of(edges).into_iter().enumerate() {
+        graph.push_str(&format!("  subgraph cluster_{index} {{\n"));
+        graph.push_str(&format!("    label={};\n", dot_string(module)));
+        for node in nodes {
+          graph.push_str(&format!(
+            "    {} [label={}];\n",
+            dot_string(&node.id()),
+            dot_string(node.label())
+          ));
+        }
+        graph.push_str("  }\n");
+      }
+
+      for edge in edges {
+        let mut attrs = vec![];
+        if edge.boundary != Boundary::Sync {
+          attrs.push("style=dashed".to_owned());
+        }
+        if let Some(label) = label(edge) {
+          attrs.push(format!("label={}", dot_string(&label)));
+        }
+
+        graph.push_str(&format!(
+          "  {} -> {}",
+          dot_string(&edge.from.id()),
+          dot_string(&edge.to.id())
+        ));
+        if !attrs.is_empty() {
+          graph.push_str(&format!(" [{}]", attrs.join(", ")));
+        }
+        graph.push_str(";\n");
+      }
+      graph.push_str("}\n");
+    }
+    GraphFormat::Mermaid => {
+      // Names like `UI.Card` aren't valid ids, so each gets a numbered one
+      let mut ids = vec![];
+      graph.push_str("flowchart TD\n");
+      for (index, (module, nodes)) in modules_of(edges).into_iter().enumerate() {
+        graph.push_str(&format!(
+          "  subgraph m{index} [{}]\n",
+          mermaid_string(module)
+        ));
+        for node in nodes {
+          graph.push_str(&format!(
+            "    n{}[{}]\n",
+            ids.len(),
+            mermaid_string(node.label())
+          ));
+          ids.push(node);
+        }
+        graph.push_str("  end\n");
+      }
+
+      let id = |node: &Node| ids.iter().position(|n| *n == node).unwrap();
+      for edge in edges {
+        let (from, to) = (id(&edge.from), id(&edge.to));
+        let arrow = match edge.boundary {
+          Boundary::Sync => "-->",
+          _ => "-.->",
+        };
+
+        match label(edge) {
+          Some(label) => graph.push_str(&format!("  n{from} {arrow}|{label}| n{to}\n")),
+          None => graph.push_str(&format!("  n{from} {arrow} n{to}\n")),
+        }
+      }
+    }
+  }
+
+  graph
+}
+
+// A quoted DOT id, where only `"` and `\` need escaping
+fn dot_string(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for char in value.chars() {
+    match char {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      char => quoted.push(char),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+fn mermaid_string(value: &str) -> String {
+  format!("\"{}\"", value.replace('"', "#quot;"))
+}
+
+#[cfg(test)]
+mod test {
+  use super::{compile, dot_string, edges, render_graph, GraphFormat};
+  use framework_js::Options;
+  use std::path::PathBuf;
+
+  #[test]
+  fn renders_are_graphed() {
+    let module = |name: &str, source: &str| {
+      let file = PathBuf::from("/app").join(name);
+      compile(
+        file,
+        name.to_owned(),
+        source.to_owned(),
+        &Options::default(),
+      )
+      .unwrap()
+    };
+    let modules = [
+      module(
+        "page.tsx",
+        "import * as UI from './ui';
+         import Card from './card.js';
+         const Title = () => <h1>Title</h1>;
+         async function Feed() { return <ul>{await items()}</ul>; }
+         export default function Page() {
+           return <main><Title /><Feed /><Feed /><UI.Card /><Card /></main>;
+         }",
+      ),
+      module(
+        "ui/index.tsx",
+        "const Title = () => <h2>UI</h2>;
+         export function Card() { return <Title />; }",
+      ),
+      module("card.tsx", "export default () => <p>Card</p>;"),
+    ];
+
+    let edges = edges(&modules);
+    assert_eq!(
+      render_graph(&edges, GraphFormat::Dot),
+      "digraph components {
+  subgraph cluster_0 {
+    label=\"page.tsx\";
+    \"page.tsx#Page\" [label=\"Page\"];
+    \"page.tsx#Title\" [label=\"Title\"];
+    \"page.tsx#Feed\" [label=\"Feed\"];
+  }
+  subgraph cluster_1 {
+    label=\"ui/index.tsx\";
+    \"ui/index.tsx#Card\" [label=\"Card\"];
+    \"ui/index.tsx#Title\" [label=\"Title\"];
+  }
+  subgraph cluster_2 {
+    label=\"card.tsx\";
+    \"card.tsx#default\" [label=\"default\"];
+  }
+  \"page.tsx#Page\" -> \"page.tsx#Title\";
+  \"page.tsx#Page\" -> \"page.tsx#Feed\" [style=dashed, label=\"async\"];
+  \"page.tsx#Page\" -> \"ui/index.tsx#Card\" [style=dashed, label=\"async, member\"];
+  \"page.tsx#Page\" -> \"card.tsx#default\" [style=dashed, label=\"async\"];
+  \"ui/index.tsx#Card\" -> \"ui/index.tsx#Title\";
+}
+"
+    );
+    assert_eq!(
+      render_graph(&edges[..2], GraphFormat::Mermaid),
+      "flowchart TD
+  subgraph m0 [\"page.tsx\"]
+    n0[\"Page\"]
+    n1[\"Title\"]
+    n2[\"Feed\"]
+  end
+  n0 --> n1
+  n0 -.->|async| n2
+"
+    );
+  }
+
+  #[test]
+  fn dot_strings_are_escaped() {
+    assert_eq!(dot_string("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+    assert_eq!(dot_string("é"), "\"é\"");
+  }
+}
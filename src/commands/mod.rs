@@ -1,3 +1,12 @@
+mod build;
+mod cache;
+mod config;
+mod flags;
+mod graph;
+mod path;
 mod testing;
+mod tsconfig;
 
+pub use build::{build, BuildCommandInfo};
+pub use graph::{graph, GraphCommandInfo};
 pub use testing::{testing, TestCommandInfo};
@@ -1,10 +1,10 @@
 use std::{
   env,
   error::Error,
-  path::{Component, PathBuf},
+  path::{Component, Path, PathBuf},
 };
 
-pub fn normalize_path(path: &PathBuf) -> PathBuf {
+pub fn normalize_path(path: &Path) -> PathBuf {
   let mut components = path.components().peekable();
   let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().cloned() {
     components.next();
@@ -38,5 +38,5 @@ pub fn make_abs_path(path: PathBuf) -> Result<PathBuf, Box<dyn Error>> {
     env::current_dir()?.join(path)
   };
 
-  return Ok(normalize_path(&path));
+  Ok(normalize_path(&path))
 }
@@ -1,74 +1,119 @@
-use crate::{transpiler::TranspileVisitor, utils};
-use anyhow::Context;
+use crate::commands::{build::write_output, flags::CompileFlags, path};
 use clap::Args;
-use std::{fs, path::PathBuf, sync::Arc};
-use swc::{
-  self,
-  config::{Config, JscConfig, Options},
-  try_with_handler,
+use std::{
+  fs::File,
+  io::{self, Read, Write},
+  path::{Path, PathBuf},
+  process,
 };
-use swc_common::{SourceMap, GLOBALS};
-use swc_core::ecma::visit::{as_folder, FoldWith};
-use swc_ecma_ast::EsVersion;
-use swc_ecma_parser::{Syntax, TsConfig};
 
 #[derive(Debug, Args)]
 pub struct TestCommandInfo {
+  /// `-` reads stdin
   input: PathBuf,
+  /// `-` writes to stdout
   output: PathBuf,
 
-  #[arg(short, long)]
-  minify: bool,
+  /// What stdin is called in diagnostics, and where its imports resolve from
+  #[arg(long, default_value = "stdin.tsx")]
+  stdin_filename: PathBuf,
+
+  #[command(flatten)]
+  flags: CompileFlags,
 }
 
 pub fn testing(info: TestCommandInfo) {
-  let input_file = utils::path::make_abs_path(info.input).unwrap();
-  let output_file = utils::path::make_abs_path(info.output).unwrap();
-
-  let cm = Arc::<SourceMap>::default();
-
-  let c = swc::Compiler::new(cm.clone());
-
-  let code = GLOBALS
-    .set(&Default::default(), || {
-      try_with_handler(cm.clone(), Default::default(), |handler| {
-        let fm = cm.load_file(&input_file).expect("failed to load file");
-
-        let output = c
-          .parse_js(
-            fm,
-            handler,
-            EsVersion::EsNext,
-            Syntax::Typescript(TsConfig {
-              tsx: true,
-              ..Default::default()
-            }),
-            swc::config::IsModule::Bool(true),
-            None,
-          )
-          .context("failed to parse file")?;
-
-        let output = output.fold_with(&mut as_folder(TranspileVisitor::new(&c)));
-
-        c.process_js(
-          handler,
-          output,
-          &Options {
-            config: Config {
-              minify: info.minify.into(),
-              jsc: JscConfig {
-                target: Some(EsVersion::EsNext),
-                ..JscConfig::default()
-              },
-              ..Config::default()
-            },
-            ..Options::default()
-          },
-        )
-        .context("failed to process file")
-      })
-    })
-    .unwrap();
-
-  fs::write(output_file, code.code).unwrap();
+  if let Err(error) = run(info, io::stdin().lock(), io::stdout().lock()) {
+    eprintln!("{error}");
+    process::exit(1);
+  }
+}
+
+// `stdin` and `stdout` are only used for `-`
+fn run(info: TestCommandInfo, mut stdin: impl Read, stdout: impl Write) -> Result<(), String> {
+  let from_stdin = info.input == Path::new("-");
+  let input_file = match from_stdin {
+    true => path::make_abs_path(info.stdin_filename).unwrap(),
+    false => path::make_abs_path(info.input).unwrap(),
+  };
+
+  let mut source = String::new();
+  match from_stdin {
+    true => stdin.read_to_string(&mut source),
+    false => File::open(&input_file).and_then(|mut file| file.read_to_string(&mut source)),
+  }
+  .map_err(|error| format!("failed to load {}: {error}", input_file.display()))?;
+
+  let settings = info.flags.load().map_err(|error| format!("{error:#}"))?;
+  let options = settings
+    .options(input_file)
+    .map_err(|error| format!("{error:#}"))?;
+
+  let output = framework_js::transpile(source, &options).map_err(|error| error.to_string())?;
+  match info.output == Path::new("-") {
+    true => write_stdout(stdout, &output.code).map_err(|error| error.to_string()),
+    false => write_output(&path::make_abs_path(info.output).unwrap(), output)
+      .map_err(|error| error.to_string()),
+  }
+}
+
+// Whoever reads it can stop early, like `| head` does
+fn write_stdout(mut stdout: impl Write, code: &str) -> io::Result<()> {
+  match stdout
+    .write_all(code.as_bytes())
+    .and_then(|_| stdout.flush())
+  {
+    Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+    result => result,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{run, TestCommandInfo};
+  use clap::Parser;
+  use std::io::{self, Write};
+
+  #[derive(Parser)]
+  struct Cli {
+    #[command(flatten)]
+    info: TestCommandInfo,
+  }
+
+  struct ClosedPipe;
+
+  impl Write for ClosedPipe {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+      Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Err(io::ErrorKind::BrokenPipe.into())
+    }
+  }
+
+  #[test]
+  fn stdin_is_compiled_to_stdout() {
+    let dir = std::env::temp_dir().join(format!("framework-js-testing-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let filename = dir.join("page.tsx");
+    let info = || {
+      let args = ["framework-js", "-", "-", "--stdin-filename"];
+      Cli::parse_from(args.into_iter().chain(filename.to_str())).info
+    };
+    let source = "export default () => <p>Hello</p>;".as_bytes();
+
+    let mut stdout = vec![];
+    run(info(), source, &mut stdout).unwrap();
+    let code = String::from_utf8(stdout).unwrap();
+    assert!(code.contains("`<p>Hello</p>`"), "{code}");
+
+    // Closed before everything was written
+    run(info(), source, ClosedPipe).unwrap();
+
+    let error = run(info(), "export default () => <p>".as_bytes(), vec![]).unwrap_err();
+    assert!(error.contains("page.tsx"), "{error}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
 }
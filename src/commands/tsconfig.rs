@@ -0,0 +1,212 @@
+use anyhow::Context;
+use framework_js::{JsxMode, ModuleKind, Options, PathAliases, JSX_IMPORT_SOURCE};
+use jsonc_parser::ParseOptions;
+use serde::Deserialize;
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+use swc_ecma_ast::EsVersion;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsConfigJson {
+  extends: Option<String>,
+  #[serde(default)]
+  compiler_options: CompilerOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptions {
+  target: Option<String>,
+  module: Option<String>,
+  jsx: Option<String>,
+  jsx_import_source: Option<String>,
+  base_url: Option<PathBuf>,
+  paths: Option<BTreeMap<String, Vec<String>>>,
+}
+
+// The parts of the project's tsconfig that change the output, with
+// `extends` applied and every path absolute
+#[derive(Debug, Default)]
+pub struct TsConfig {
+  target: Option<EsVersion>,
+  module: Option<ModuleKind>,
+  jsx: Option<String>,
+  jsx_import_source: Option<String>,
+  base_url: Option<PathBuf>,
+  paths: Option<BTreeMap<String, Vec<String>>>,
+}
+
+impl TsConfig {
+  pub fn load(path: &Path) -> anyhow::Result<TsConfig> {
+    let text =
+      fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    // It's JSON with comments and trailing commas
+    let value = jsonc_parser::parse_to_serde_value(&text, &ParseOptions::default())
+      .with_context(|| format!("invalid tsconfig {}", path.display()))?
+      .unwrap_or_default();
+    let json = serde_json::from_value::<TsConfigJson>(value)
+      .with_context(|| format!("invalid tsconfig {}", path.display()))?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut config = match json.extends {
+      // Only relative ones, there's no `node_modules` lookup for packages
+      Some(extends) if extends.starts_with('.') => {
+        let mut extends = dir.join(extends);
+        if extends.extension().is_none() {
+          extends.set_extension("json");
+        }
+        TsConfig::load(&extends)?
+      }
+      _ => TsConfig::default(),
+    };
+
+    let options = json.compiler_options;
+    if let Some(target) = options.target {
+      config.target = Some(parse_target(&target)?);
+    }
+    if let Some(module) = options.module {
+      // Everything else, `node16` and friends too, is left as ES modules
+      config.module = Some(match module.to_lowercase().as_str() {
+        "commonjs" => ModuleKind::CommonJs,
+        _ => ModuleKind::Esm,
+      });
+    }
+    if options.jsx.is_some() {
+      config.jsx = options.jsx;
+    }
+    if options.jsx_import_source.is_some() {
+      config.jsx_import_source = options.jsx_import_source;
+    }
+    if let Some(base_url) = options.base_url {
+      config.base_url = Some(dir.join(base_url));
+    }
+    if let Some(paths) = options.paths {
+      // Without a `baseUrl` they're relative to the tsconfig they're in
+      let base = config.base_url.clone().unwrap_or_else(|| dir.to_owned());
+      let paths = paths
+        .into_iter()
+        .map(|(pattern, substitutions)| {
+          let substitutions = substitutions
+            .iter()
+            .map(|substitution| base.join(substitution).to_string_lossy().into_owned())
+            .collect();
+          (pattern, substitutions)
+        })
+        .collect();
+      config.paths = Some(paths);
+    }
+
+    Ok(config)
+  }
+
+  // The closest `tsconfig.json` to `dir`
+  pub fn discover(dir: &Path) -> anyhow::Result<Option<TsConfig>> {
+    match dir
+      .ancestors()
+      .map(|dir| dir.join("tsconfig.json"))
+      .find(|p| p.is_file())
+    {
+      Some(path) => TsConfig::load(&path).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  // A file's `@jsxImportSource` pragma is read when it's compiled
+  pub fn apply(&self, options: &mut Options) {
+    if let Some(target) = self.target {
+      options.target = target;
+    }
+    if let Some(module) = self.module {
+      options.module = module;
+    }
+    options.jsx_mode = self.jsx_mode();
+    if self.base_url.is_some() || self.paths.is_some() {
+      options.aliases = Some(PathAliases::new(
+        self.base_url.clone(),
+        self.paths.clone().unwrap_or_default(),
+      ));
+    }
+  }
+
+  fn jsx_mode(&self) -> JsxMode {
+    let import_source = self.jsx_import_source.clone();
+    match self.jsx.as_deref() {
+      Some("react") => JsxMode::Classic,
+      Some("react-jsx" | "react-jsxdev") => match import_source {
+        Some(source) if source == JSX_IMPORT_SOURCE => JsxMode::ServerComponents,
+        source => JsxMode::Automatic(source.unwrap_or_else(|| "react".to_owned())),
+      },
+      // `preserve` leaves the JSX to us, unless it's meant for someone else
+      _ => match import_source {
+        Some(source) if source != JSX_IMPORT_SOURCE => JsxMode::Automatic(source),
+        _ => JsxMode::ServerComponents,
+      },
+    }
+  }
+}
+
+// `es5`, `ES2019`, `esnext`... the way TS spells them
+pub fn parse_target(target: &str) -> anyhow::Result<EsVersion> {
+  let target = match target.to_lowercase().as_str() {
+    "es6" => "es2015".to_owned(),
+    target => target.to_owned(),
+  };
+  match serde_json::from_value(serde_json::Value::String(target.clone())) {
+    Ok(version) => Ok(version),
+    // Newer than swc knows about
+    Err(_) if target.starts_with("es20") && target[2..].parse::<u16>().is_ok() => {
+      Ok(EsVersion::EsNext)
+    }
+    Err(_) => Err(anyhow::anyhow!("unknown target `{target}`")),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::TsConfig;
+  use framework_js::{JsxMode, Options};
+  use std::fs;
+  use swc_ecma_ast::EsVersion;
+
+  #[test]
+  fn tsconfig_extends_and_applies() {
+    let root = std::env::temp_dir().join(format!("framework-js-tsconfig-{}", std::process::id()));
+    fs::create_dir_all(root.join("app/src")).unwrap();
+    fs::write(
+      root.join("tsconfig.base.json"),
+      r#"{
+        // Shared by every package
+        "compilerOptions": { "target": "ES2019", "jsx": "react-jsx" },
+      }"#,
+    )
+    .unwrap();
+    fs::write(
+      root.join("app/tsconfig.json"),
+      r#"{
+        "extends": "../tsconfig.base.json",
+        "compilerOptions": { "jsxImportSource": "framework-js", "paths": { "@/*": ["src/*"] } }
+      }"#,
+    )
+    .unwrap();
+
+    let config = TsConfig::discover(&root.join("app/src")).unwrap().unwrap();
+    let mut options = Options::default();
+    config.apply(&mut options);
+    assert_eq!(options.target, EsVersion::Es2019);
+    assert_eq!(options.jsx_mode, JsxMode::ServerComponents);
+
+    let aliases = options.aliases.unwrap();
+    let importer = root.join("app/src/pages/index.tsx");
+    assert_eq!(
+      aliases.resolve("@/Button", &importer).as_deref(),
+      Some("../Button")
+    );
+    assert_eq!(aliases.resolve("app/src/Button", &importer), None);
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
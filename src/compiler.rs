@@ -0,0 +1,398 @@
+use crate::{
+  resolve::{PathAliases, ResolveAliases},
+  ComponentInfo, Render, TranspileVisitor, VisitorOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, sync::Arc};
+use swc::{
+  config::{Config, JscConfig, ModuleConfig, SourceMapsConfig, TransformConfig},
+  try_with_handler,
+};
+use swc_common::{comments::SingleThreadedComments, FileName, SourceMap, Spanned, GLOBALS};
+use swc_core::ecma::visit::{Visit, VisitMutWith, VisitWith};
+use swc_ecma_ast::{EsVersion, Function, Program};
+use swc_ecma_parser::{EsConfig, Syntax, TsConfig};
+use swc_ecma_transforms_module::common_js;
+use swc_ecma_transforms_react::Runtime;
+
+// The `jsxImportSource` that marks JSX as ours
+pub const JSX_IMPORT_SOURCE: &str = "framework-js";
+
+// Who compiles the JSX of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub enum JsxMode {
+  // Us, into server components
+  #[default]
+  ServerComponents,
+  // React, into `React.createElement` calls
+  Classic,
+  // React's automatic runtime, imported from this source
+  Automatic(String),
+}
+
+// The module system of the output, the input is always ES modules
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleKind {
+  #[default]
+  Esm,
+  // `require` and `exports`, the runtime import included
+  #[value(name = "commonjs")]
+  CommonJs,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Options {
+  // Used in diagnostics and the source map
+  pub filename: PathBuf,
+  // What the output is allowed to use
+  pub target: EsVersion,
+  // Parse TypeScript, or plain JS
+  pub typescript: bool,
+  pub jsx: bool,
+  pub jsx_mode: JsxMode,
+  // Rewrites aliased imports into relative ones
+  pub aliases: Option<PathAliases>,
+  pub module: ModuleKind,
+  pub minify: bool,
+  pub source_map: bool,
+  pub visitor: VisitorOptions,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Options {
+      filename: PathBuf::from("input.tsx"),
+      target: EsVersion::EsNext,
+      typescript: true,
+      jsx: true,
+      jsx_mode: JsxMode::default(),
+      aliases: None,
+      module: ModuleKind::default(),
+      minify: false,
+      source_map: false,
+      visitor: VisitorOptions::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Output {
+  pub code: String,
+  pub map: Option<String>,
+  // Every component the transform found
+  pub components: Vec<ComponentInfo>,
+  // And every one of them rendering another
+  pub renders: Vec<Render>,
+  // Every package-like import and the relative one the aliases made it,
+  // they depend on which files exist and not only on the source
+  pub resolved_imports: Vec<(String, Option<String>)>,
+}
+
+// Everything that went wrong, parse errors included
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+  pub messages: Vec<String>,
+}
+
+impl fmt::Display for Diagnostics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.messages.join("\n"))
+  }
+}
+
+impl std::error::Error for Diagnostics {}
+
+pub fn transpile<S: Into<String>>(source: S, options: &Options) -> Result<Output, Diagnostics> {
+  transpile_with_source_map(Arc::default(), source, options)
+}
+
+// For builds of many files, every thread can add its files to the same `cm`
+pub fn transpile_with_source_map<S: Into<String>>(
+  cm: Arc<SourceMap>,
+  source: S,
+  options: &Options,
+) -> Result<Output, Diagnostics> {
+  let c = swc::Compiler::new(cm.clone());
+  let (mut components, mut renders, mut resolved_imports) = (vec![], vec![], vec![]);
+
+  let output = GLOBALS.set(&Default::default(), || {
+    try_with_handler(cm.clone(), Default::default(), |handler| {
+      let fm = cm.new_source_file(FileName::Real(options.filename.clone()), source.into());
+      // Only read for the `@jsxImportSource` pragma, the output has none
+      let comments = SingleThreadedComments::default();
+
+      let program = c.parse_js(
+        fm,
+        handler,
+        EsVersion::EsNext,
+        match options.typescript {
+          true => Syntax::Typescript(TsConfig {
+            tsx: options.jsx,
+            ..Default::default()
+          }),
+          false => Syntax::Es(EsConfig {
+            jsx: options.jsx,
+            ..Default::default()
+          }),
+        },
+        swc::config::IsModule::Bool(true),
+        Some(&comments),
+      )?;
+
+      // The pragma wins over the tsconfig, like it does in TS
+      let jsx_mode = match (
+        &options.jsx_mode,
+        jsx_import_source_pragma(&comments, &program),
+      ) {
+        (JsxMode::Classic, _) | (_, None) => options.jsx_mode.clone(),
+        (_, Some(source)) if source == JSX_IMPORT_SOURCE => JsxMode::ServerComponents,
+        (_, Some(source)) => JsxMode::Automatic(source),
+      };
+
+      let mut program = program;
+      if let Some(aliases) = &options.aliases {
+        let mut resolve = ResolveAliases {
+          aliases,
+          importer: &options.filename,
+          resolved: vec![],
+        };
+        program.visit_mut_with(&mut resolve);
+        resolved_imports = resolve.resolved;
+      }
+
+      let mut visitor = TranspileVisitor::with_options(options.visitor.clone());
+      visitor.target = options.target;
+      let mut transform = TransformConfig::default();
+      match &jsx_mode {
+        JsxMode::ServerComponents => {}
+        JsxMode::Classic => transform.react.runtime = Some(Runtime::Classic),
+        JsxMode::Automatic(source) => {
+          transform.react.runtime = Some(Runtime::Automatic);
+          transform.react.import_source = Some(source.clone());
+        }
+      }
+
+      let program = match jsx_mode {
+        JsxMode::ServerComponents => {
+          program.visit_mut_with(&mut visitor);
+          (components, renders) = visitor.take_components();
+          program
+        }
+        // Left for swc's React transform
+        _ => program,
+      };
+      // The visitor reports unsupported syntax through the handler, which
+      // fails the compile with every one of them
+      if handler.has_errors() {
+        anyhow::bail!("failed to transpile");
+      }
+
+      // swc only lowers `async function*`s together with `async` functions,
+      // so ES2017 output with them is lowered like ES2016
+      let target = match options.target == EsVersion::Es2017 && has_async_generators(&program) {
+        true => EsVersion::Es2016,
+        false => options.target,
+      };
+
+      c.process_js(
+        handler,
+        program,
+        &swc::config::Options {
+          config: Config {
+            minify: options.minify.into(),
+            module: match options.module {
+              ModuleKind::Esm => None,
+              ModuleKind::CommonJs => Some(ModuleConfig::CommonJs(common_js::Config::default())),
+            },
+            source_maps: Some(SourceMapsConfig::Bool(options.source_map)),
+            jsc: JscConfig {
+              target: Some(target),
+              transform: Some(transform).into(),
+              ..JscConfig::default()
+            },
+            ..Config::default()
+          },
+          source_maps: Some(SourceMapsConfig::Bool(options.source_map)),
+          ..swc::config::Options::default()
+        },
+      )
+    })
+  });
+
+  match output {
+    Ok(output) => Ok(Output {
+      code: output.code,
+      map: output.map,
+      components,
+      renders,
+      resolved_imports,
+    }),
+    Err(error) => Err(Diagnostics {
+      messages: error.chain().map(|e| e.to_string()).collect(),
+    }),
+  }
+}
+
+// `/** @jsxImportSource x */`, only in the comments before the first statement
+fn jsx_import_source_pragma(
+  comments: &SingleThreadedComments,
+  program: &Program,
+) -> Option<String> {
+  comments.with_leading(program.span().lo, |comments| {
+    comments.iter().find_map(|comment| {
+      let (_, rest) = comment.text.split_once("@jsxImportSource")?;
+      rest.split_whitespace().next().map(|s| s.to_owned())
+    })
+  })
+}
+
+fn has_async_generators(program: &Program) -> bool {
+  struct Finder(bool);
+  impl Visit for Finder {
+    fn visit_function(&mut self, function: &Function) {
+      self.0 |= function.is_async && function.is_generator;
+      function.visit_children_with(self);
+    }
+  }
+
+  let mut finder = Finder(false);
+  program.visit_with(&mut finder);
+  finder.0
+}
+
+#[cfg(test)]
+mod test {
+  use super::{transpile, JsxMode, ModuleKind, Options};
+  use swc_ecma_ast::EsVersion;
+
+  #[test]
+  fn transpile_reports_diagnostics() {
+    let output = transpile(
+      "export default () => <p>Hello</p>;",
+      &Options {
+        source_map: true,
+        ..Options::default()
+      },
+    )
+    .unwrap();
+    assert!(output.code.contains("`<p>Hello</p>`"));
+    assert!(output.map.is_some());
+
+    let diagnostics = transpile("export default () => <p>Hello</div>;", &Options::default())
+      .unwrap_err()
+      .to_string();
+    assert!(diagnostics.contains("input.tsx"), "{diagnostics}");
+
+    // The visitor's own errors too, without panicking
+    let diagnostics = transpile(
+      "export default () => <main>{{ a: 1 }}<p style={{ get a() { return 1; } }} /></main>;",
+      &Options::default(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+      diagnostics.contains("Objects are not valid"),
+      "{diagnostics}"
+    );
+    assert!(diagnostics.contains("plain `name: value`"), "{diagnostics}");
+
+    let diagnostics = transpile(
+      "export default (props) => <ErrorBoundary {...props}><p /></ErrorBoundary>;",
+      &Options::default(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(diagnostics.contains("<ErrorBoundary>"), "{diagnostics}");
+
+    let diagnostics = transpile(
+      "const red = css`color: ${color};`; export default () => <p class={red} />;",
+      &Options::default(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(diagnostics.contains("`css` templates"), "{diagnostics}");
+  }
+
+  #[test]
+  fn output_stays_within_the_target() {
+    let source = "async function* Results() { yield <li>{[1].at?.(0) ?? 2}</li>; }
+                  export default function Page() { return <ul><Results /></ul>; }";
+
+    let output = transpile(source, &Options::default()).unwrap();
+    assert!(output.code.contains("async function* Results"));
+    assert!(output.code.contains("Promise.allSettled("));
+
+    let output = transpile(
+      source,
+      &Options {
+        target: EsVersion::Es2017,
+        ..Options::default()
+      },
+    )
+    .unwrap();
+    for newer in ["async function*", "?.", "??", "Promise.allSettled("] {
+      assert!(!output.code.contains(newer), "{newer} in {}", output.code);
+    }
+    assert!(output
+      .code
+      .contains("global.___FRAMEWORK_JS_ALL_SETTLED___("));
+  }
+
+  #[test]
+  fn commonjs_output_requires_the_runtime() {
+    let mut options = Options {
+      module: ModuleKind::CommonJs,
+      ..Options::default()
+    };
+    options.visitor.runtime.import_source = Some("framework-js/runtime".to_owned());
+
+    let output = transpile(
+      "import Header from './header';
+       export default () => <main><Header /></main>;",
+      &options,
+    )
+    .unwrap();
+    assert!(output.code.contains("require(\"framework-js/runtime\");"));
+    assert!(output.code.contains("require(\"./header\")"));
+    assert!(output.code.contains("exports"));
+    assert!(!output.code.contains("import "), "{}", output.code);
+  }
+
+  #[test]
+  fn jsx_import_source_pragma_is_read_from_leading_comments() {
+    let output = transpile(
+      "/** @jsxImportSource preact */
+       export default () => <p>Hello</p>;",
+      &Options::default(),
+    )
+    .unwrap();
+    assert!(
+      output.code.contains("preact/jsx-runtime"),
+      "{}",
+      output.code
+    );
+
+    // Anywhere else it's just text
+    let output = transpile(
+      "const pragma = '@jsxImportSource preact';
+       /** @jsxImportSource preact */
+       export default () => <p>{pragma}</p>;",
+      &Options::default(),
+    )
+    .unwrap();
+    assert!(output.code.contains("`<p>"), "{}", output.code);
+
+    // And it can turn our JSX back on
+    let output = transpile(
+      "// @jsxImportSource framework-js
+       export default () => <p>Hello</p>;",
+      &Options {
+        jsx_mode: JsxMode::Automatic("react".to_owned()),
+        ..Options::default()
+      },
+    )
+    .unwrap();
+    assert!(output.code.contains("`<p>Hello</p>`"), "{}", output.code);
+  }
+}
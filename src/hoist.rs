@@ -0,0 +1,91 @@
+use crate::tpl_wrapper::TplWrapper;
+use std::collections::HashMap;
+use swc_common::util::take::Take;
+use swc_ecma_ast::{Decl, Expr, Ident, Pat, Stmt, Tpl, VarDecl, VarDeclKind, VarDeclarator};
+
+// Static html shorter than this isn't worth a constant of its own
+pub const HOIST_MIN_LENGTH: usize = 64;
+
+// Static html that's moved out of the components into module level constants,
+// so it isn't rebuilt on every render
+#[derive(Default)]
+pub struct HoistedStrings {
+  idents: HashMap<String, Ident>,
+  // In the order they were hoisted, so the output is stable
+  order: Vec<String>,
+}
+
+impl HoistedStrings {
+  // Identical html shares one constant
+  pub fn ident_for(&mut self, raw: &str) -> Ident {
+    if let Some(ident) = self.idents.get(raw) {
+      return ident.clone();
+    }
+
+    let ident: Ident = crate::utils::generate_random_variable_name(12)
+      .as_str()
+      .into();
+    self.idents.insert(raw.to_owned(), ident.clone());
+    self.order.push(raw.to_owned());
+
+    ident
+  }
+
+  pub fn hoist_tpl(&mut self, tpl: Tpl) -> Expr {
+    if tpl.exprs.is_empty() {
+      let raw = tpl
+        .quasis
+        .iter()
+        .map(|q| q.raw.as_str())
+        .collect::<String>();
+      if raw.len() < HOIST_MIN_LENGTH {
+        return Expr::Tpl(tpl);
+      }
+      return Expr::Ident(self.ident_for(&raw));
+    }
+
+    let mut wrapper = TplWrapper::new();
+    let mut exprs = tpl.exprs.into_iter();
+    for quasi in tpl.quasis {
+      match quasi.raw.len() < HOIST_MIN_LENGTH {
+        true => wrapper.append_quasi(quasi.raw.as_str()),
+        false => wrapper.append_expr(Expr::Ident(self.ident_for(&quasi.raw))),
+      }
+      if let Some(expr) = exprs.next() {
+        wrapper.append_expr(*expr);
+      }
+    }
+
+    Expr::Tpl(wrapper.build())
+  }
+
+  // `const _a = `...`, _b = `...`;`, `None` if nothing was hoisted
+  pub fn take_decl(&mut self) -> Option<Stmt> {
+    if self.order.is_empty() {
+      return None;
+    }
+
+    let decls = self
+      .order
+      .drain(..)
+      .map(|raw| {
+        let ident = self.idents.remove(&raw).unwrap();
+
+        // Kept as a template, so the raw html doesn't need re-escaping
+        let tpl = TplWrapper::from_quasi(raw);
+
+        VarDeclarator {
+          name: Pat::Ident(ident.into()),
+          init: Some(Box::new(Expr::Tpl(tpl.build()))),
+          ..VarDeclarator::dummy()
+        }
+      })
+      .collect();
+
+    Some(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+      kind: VarDeclKind::Const,
+      decls,
+      ..VarDecl::dummy()
+    }))))
+  }
+}
@@ -0,0 +1,21 @@
+#[cfg(feature = "compiler")]
+mod compiler;
+mod hoist;
+pub mod manifest;
+pub mod options;
+#[cfg(feature = "compiler")]
+mod resolve;
+mod tpl_wrapper;
+pub mod transpiler;
+mod utils;
+
+#[cfg(feature = "compiler")]
+pub use compiler::{
+  transpile, transpile_with_source_map, Diagnostics, JsxMode, ModuleKind, Options, Output,
+  JSX_IMPORT_SOURCE,
+};
+pub use manifest::{ComponentInfo, Render};
+pub use options::{RuntimeOptions, Streaming, VisitorOptions};
+#[cfg(feature = "compiler")]
+pub use resolve::PathAliases;
+pub use transpiler::TranspileVisitor;
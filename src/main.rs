@@ -1,9 +1,6 @@
 use clap::{Parser, Subcommand};
 
 mod commands;
-mod tpl_wrapper;
-mod transpiler;
-mod utils;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -15,6 +12,10 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
   Test(commands::TestCommandInfo),
+  /// Compile every component in a directory, in parallel
+  Build(commands::BuildCommandInfo),
+  /// Print which components render which, as DOT or Mermaid
+  Graph(commands::GraphCommandInfo),
 }
 
 fn main() {
@@ -22,5 +23,7 @@ fn main() {
 
   match cli.command {
     Commands::Test(info) => commands::testing(info),
+    Commands::Build(info) => commands::build(info),
+    Commands::Graph(info) => commands::graph(info),
   }
 }
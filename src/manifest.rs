@@ -0,0 +1,73 @@
+use crate::transpiler::VarType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use swc_core::ecma::visit::{Visit, VisitWith};
+use swc_ecma_ast::{Expr, MemberExpr, MemberProp};
+
+// A component the visitor found, for routing and prefetch tooling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentInfo {
+  pub name: String,
+  // What the module exports it as, `default` included
+  pub export: Option<String>,
+  pub var_type: VarType,
+  pub kind: ComponentInfoKind,
+  // The components it renders behind a `<div id>` placeholder
+  pub async_children: Vec<String>,
+  // The `___FRAMEWORK_JS_*___` functions its transpiled code calls
+  pub runtime_helpers: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComponentInfoKind {
+  Function,
+  Class,
+  // Inlined wherever it's rendered
+  Static,
+}
+
+// One component rendering another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Render {
+  // The closest component it's rendered in, `None` outside of every one
+  pub from: Option<String>,
+  pub to: String,
+  pub boundary: Boundary,
+  // Rendered as `<UI.Card />`, those are always treated as async
+  pub member: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Boundary {
+  // Rendered in place
+  Sync,
+  // Placed once it resolves
+  Async,
+  // Every chunk is placed as it's yielded
+  Stream,
+}
+
+// Every runtime helper `node` calls, before the runtime global is renamed
+pub fn runtime_helpers<N: VisitWith<HelperFinder>>(node: &N) -> BTreeSet<String> {
+  let mut finder = HelperFinder(BTreeSet::new());
+  node.visit_with(&mut finder);
+  finder.0
+}
+
+pub struct HelperFinder(BTreeSet<String>);
+
+impl Visit for HelperFinder {
+  fn visit_member_expr(&mut self, member: &MemberExpr) {
+    member.visit_children_with(self);
+
+    if let (Expr::Ident(obj), MemberProp::Ident(prop)) = (&*member.obj, &member.prop) {
+      if obj.sym == "global" && prop.sym.starts_with("___FRAMEWORK_JS_") {
+        self.0.insert(prop.sym.to_string());
+      }
+    }
+  }
+}
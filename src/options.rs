@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Everything that changes what `TranspileVisitor` emits. Also what the Wasm
+// plugin takes as its JSON options, and the config file's transform settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct VisitorOptions {
+  // Long static html is moved into module level constants
+  pub hoist_static_templates: bool,
+  // Static `style={{...}}` objects become atomic classes in one stylesheet
+  pub atomic_styles: bool,
+  pub runtime: RuntimeOptions,
+  // JSX attribute renames, on top of `className` => `class`
+  pub attributes: BTreeMap<String, String>,
+  pub streaming: Streaming,
+}
+
+impl Default for VisitorOptions {
+  fn default() -> Self {
+    VisitorOptions {
+      hoist_static_templates: true,
+      atomic_styles: false,
+      runtime: RuntimeOptions::default(),
+      attributes: BTreeMap::new(),
+      streaming: Streaming::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct RuntimeOptions {
+  // Imported at the top of every module for its side effects, it's what
+  // sets up the `___FRAMEWORK_JS_*___` helpers
+  pub import_source: Option<String>,
+  // The object the helpers live on
+  pub global: String,
+}
+
+impl Default for RuntimeOptions {
+  fn default() -> Self {
+    RuntimeOptions {
+      import_source: None,
+      global: "global".to_owned(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "compiler", derive(clap::ValueEnum))]
+pub enum Streaming {
+  // `async function*` components stream every chunk as soon as it's yielded
+  #[default]
+  Chunks,
+  // They're placed all at once, after their last chunk
+  Buffered,
+}
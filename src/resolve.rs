@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::{
+  cmp::Reverse,
+  ffi::OsString,
+  path::{Path, PathBuf},
+};
+use swc_core::ecma::visit::{VisitMut, VisitMutWith};
+use swc_ecma_ast::{CallExpr, Callee, ExportAll, Expr, ImportDecl, Lit, NamedExport, Str};
+
+// What an import without an extension could point to, in the order TS tries them
+const EXTENSIONS: [&str; 6] = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+// `baseUrl` and `paths` from a tsconfig, so aliased imports still resolve
+// once the output runs without TypeScript
+#[derive(Debug, Clone, Serialize)]
+pub struct PathAliases {
+  // Without one only `paths` are resolved, so they have to be absolute
+  base_url: Option<PathBuf>,
+  // Longest prefix first, that's the one TS picks when several match
+  paths: Vec<(String, Vec<String>)>,
+}
+
+impl PathAliases {
+  pub fn new<I: IntoIterator<Item = (String, Vec<String>)>>(
+    base_url: Option<PathBuf>,
+    paths: I,
+  ) -> Self {
+    let mut paths = paths.into_iter().collect::<Vec<_>>();
+    paths.sort_by_key(|(pattern, _)| Reverse(pattern.find('*').unwrap_or(pattern.len())));
+    PathAliases { base_url, paths }
+  }
+
+  // The relative import `specifier` becomes in `importer`, if it's aliased
+  pub fn resolve(&self, specifier: &str, importer: &Path) -> Option<String> {
+    if specifier.starts_with('.') || specifier.starts_with('/') {
+      return None;
+    }
+
+    let target = self.resolve_path(specifier)?;
+    let relative = pathdiff::diff_paths(target, importer.parent()?)?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    Some(match relative.starts_with("../") {
+      true => relative,
+      false => format!("./{relative}"),
+    })
+  }
+
+  fn resolve_path(&self, specifier: &str) -> Option<PathBuf> {
+    for (pattern, substitutions) in &self.paths {
+      let Some(matched) = match_pattern(pattern, specifier) else {
+        continue;
+      };
+      let candidates = substitutions
+        .iter()
+        .map(|substitution| {
+          let substitution = PathBuf::from(substitution.replacen('*', matched, 1));
+          match &self.base_url {
+            Some(base_url) => base_url.join(substitution),
+            None => substitution,
+          }
+        })
+        .collect::<Vec<_>>();
+      // The first one that exists, or the first one if none do
+      return candidates
+        .iter()
+        .find_map(|candidate| find(candidate))
+        .or(candidates.first().cloned());
+    }
+
+    // Without an alias only files under `baseUrl` are, everything else is a package
+    find(&self.base_url.as_ref()?.join(specifier))
+  }
+}
+
+fn match_pattern<'a>(pattern: &str, specifier: &'a str) -> Option<&'a str> {
+  match pattern.split_once('*') {
+    Some((prefix, suffix)) => specifier.strip_prefix(prefix)?.strip_suffix(suffix),
+    None => (pattern == specifier).then_some(""),
+  }
+}
+
+// The file `path` imports, spelled the way Node's ESM resolution needs it:
+// with an extension, and the one it's compiled to
+fn find(path: &Path) -> Option<PathBuf> {
+  let with_extension = |path: &Path, extension: &str| {
+    let mut path = OsString::from(path);
+    path.push(".");
+    path.push(extension);
+    Some(PathBuf::from(path)).filter(|path| path.is_file())
+  };
+
+  let file = match path.is_file() {
+    true => path.to_owned(),
+    false => EXTENSIONS.iter().find_map(|extension| {
+      with_extension(path, extension).or_else(|| with_extension(&path.join("index"), extension))
+    })?,
+  };
+  Some(match file.extension().and_then(|e| e.to_str()) {
+    // Everything but `.mjs` and `.cjs` is built into a `.js`
+    Some("ts" | "tsx" | "jsx") => file.with_extension("js"),
+    _ => file,
+  })
+}
+
+// Rewrites the aliased imports, exports and `import()`s of a module
+pub struct ResolveAliases<'a> {
+  pub aliases: &'a PathAliases,
+  pub importer: &'a Path,
+  // Every specifier that could be aliased, and what it became
+  pub resolved: Vec<(String, Option<String>)>,
+}
+
+impl ResolveAliases<'_> {
+  fn rewrite(&mut self, src: &mut Str) {
+    if src.value.starts_with('.') || src.value.starts_with('/') {
+      return;
+    }
+
+    let resolved = self.aliases.resolve(&src.value, self.importer);
+    let import = (src.value.to_string(), resolved.clone());
+    if !self.resolved.contains(&import) {
+      self.resolved.push(import);
+    }
+    if let Some(resolved) = resolved {
+      *src = resolved.as_str().into();
+    }
+  }
+}
+
+impl VisitMut for ResolveAliases<'_> {
+  fn visit_mut_import_decl(&mut self, import: &mut ImportDecl) {
+    self.rewrite(&mut import.src);
+  }
+
+  fn visit_mut_named_export(&mut self, export: &mut NamedExport) {
+    if let Some(src) = &mut export.src {
+      self.rewrite(src);
+    }
+  }
+
+  fn visit_mut_export_all(&mut self, export: &mut ExportAll) {
+    self.rewrite(&mut export.src);
+  }
+
+  fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+    call.visit_mut_children_with(self);
+
+    if let (Callee::Import(_), Some(arg)) = (&call.callee, call.args.first_mut()) {
+      if let Expr::Lit(Lit::Str(src)) = &mut *arg.expr {
+        self.rewrite(src);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::PathAliases;
+  use std::fs;
+
+  #[test]
+  fn aliases_resolve_relative_to_the_importer() {
+    let root = std::env::temp_dir().join(format!("framework-js-resolve-{}", std::process::id()));
+    fs::create_dir_all(root.join("src/components/card")).unwrap();
+    fs::write(root.join("src/components/Button.tsx"), "").unwrap();
+    fs::write(root.join("src/components/card/index.ts"), "").unwrap();
+    fs::write(root.join("lib.js"), "").unwrap();
+
+    let aliases = PathAliases::new(
+      Some(root.clone()),
+      [
+        ("@/*".to_owned(), vec!["src/*".to_owned()]),
+        (
+          "@/components/*".to_owned(),
+          vec!["missing/*".to_owned(), "src/components/*".to_owned()],
+        ),
+      ],
+    );
+    let importer = root.join("src/pages/index.tsx");
+    let resolve = |specifier| aliases.resolve(specifier, &importer);
+
+    assert_eq!(
+      resolve("@/components/Button").as_deref(),
+      Some("../components/Button.js")
+    );
+    assert_eq!(
+      resolve("@/components/card").as_deref(),
+      Some("../components/card/index.js")
+    );
+    assert_eq!(
+      resolve("@/components/Button.tsx").as_deref(),
+      Some("../components/Button.js")
+    );
+    // Nothing to find, so it's left the way it was written
+    assert_eq!(resolve("@/pages/about").as_deref(), Some("./about"));
+    assert_eq!(resolve("lib").as_deref(), Some("../../lib.js"));
+    assert_eq!(resolve("react"), None);
+    assert_eq!(resolve("./Button"), None);
+
+    fs::remove_dir_all(root).unwrap();
+  }
+}
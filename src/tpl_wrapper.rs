@@ -1,5 +1,5 @@
 use crate::{
-  transpiler::{self, ToCreateAsync, TranspileVisitor},
+  transpiler::{ToCreateAsync, TranspileVisitor},
   utils::{self, stringify::Stringify},
 };
 use swc_common::util::take::Take;
@@ -7,6 +7,8 @@ use swc_ecma_ast::{
   CallExpr, Callee, Expr, JSXElementChild, JSXExpr, Lit, MemberExpr, Tpl, TplElement,
 };
 
+// Laid out like swc's own `Tpl`, so it moves into one as it is
+#[allow(clippy::vec_box)]
 pub struct TplWrapper {
   pub exprs: Vec<Box<Expr>>,
   pub quasis: Vec<TplElement>,
@@ -15,11 +17,17 @@ pub struct TplWrapper {
 
 impl TplWrapper {
   pub fn new() -> TplWrapper {
-    return TplWrapper {
+    TplWrapper {
       exprs: vec![],
       quasis: vec![],
       is_expr_next: false,
-    };
+    }
+  }
+
+  pub fn from_quasi<S: AsRef<str>>(quasi: S) -> TplWrapper {
+    let mut tpl = TplWrapper::new();
+    tpl.append_quasi(quasi);
+    tpl
   }
 
   pub fn append_lit(&mut self, lit: Lit) {
@@ -106,9 +114,8 @@ impl TplWrapper {
   ) {
     match element {
       JSXElementChild::JSXElement(el) => {
-        match utils::process_transformed_jsx(transpiler::transform(v, el, to_create), v, to_create)
-        {
-          utils::Processed::Async(div) => self.append_quasi(div),
+        match utils::process_jsx_element(v, el, to_create) {
+          utils::Processed::Async(div) => self.append_expr(div),
           utils::Processed::Sync(transformed) => self.append_expr(transformed),
         };
       }
@@ -134,7 +141,10 @@ impl TplWrapper {
             self.append_lit(lit);
             return;
           }
-          Expr::Object(_) => unimplemented!("Objects are not valid as a JSX child!"),
+          Expr::Object(obj) => {
+            utils::report_error(obj.span, "Objects are not valid as a JSX child");
+            return;
+          }
           _ => expr,
         };
 
@@ -151,13 +161,13 @@ impl TplWrapper {
         self.append_expr(*sc.expr);
       }
       JSXElementChild::JSXText(text) => {
-        self.append_quasi(text.value.as_str().to_owned());
+        self.append_quasi(text.value.as_str());
       }
     }
   }
 
   pub fn build(mut self) -> Tpl {
-    if self.is_expr_next == false {
+    if !self.is_expr_next {
       self.quasis.push(TplElement {
         tail: true,
         ..TplElement::dummy()
@@ -168,15 +178,16 @@ impl TplWrapper {
       }
     }
 
-    return Tpl {
+    Tpl {
       exprs: self.exprs,
       quasis: self.quasis,
       ..Tpl::dummy()
-    };
+    }
   }
 }
 
 pub trait DestructTpl {
+  #[allow(clippy::vec_box)]
   fn get_quasis_and_exprs(self) -> (Vec<TplElement>, Vec<Box<Expr>>);
 }
 
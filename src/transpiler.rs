@@ -1,25 +1,38 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap};
 
 use crate::{
+  hoist::{HoistedStrings, HOIST_MIN_LENGTH},
+  manifest::{self, Boundary, ComponentInfo, ComponentInfoKind, Render},
+  options::{Streaming, VisitorOptions},
   tpl_wrapper::TplWrapper,
-  utils::{self, stringify::Stringify},
+  utils::{
+    self,
+    css::{self, Stylesheet},
+    stringify::Stringify,
+  },
 };
 use phf::phf_map;
-use swc;
-use swc_common::{util::take::Take, Span};
-use swc_core::ecma::visit::{VisitMut, VisitMutWith};
+use serde::{Deserialize, Serialize};
+use swc_common::{util::take::Take, Span, Spanned};
+use swc_core::ecma::visit::{VisitMut, VisitMutWith, VisitWith};
 use swc_ecma_ast::{
-  ArrayLit, ArrayPat, ArrowExpr, AssignTarget, BlockStmt, BlockStmtOrExpr, CallExpr, Callee, Decl,
-  Expr, ExprOrSpread, ExprStmt, Ident, JSXAttrOrSpread, JSXAttrValue, JSXElement, JSXElementName,
-  JSXExpr, JSXMemberExpr, JSXObject, KeyValueProp, Lit, MemberExpr, MemberProp, ObjectLit,
-  ParenExpr, Pat, Prop, PropName, PropOrSpread, Regex, ReturnStmt, SimpleAssignTarget, Stmt,
-  VarDecl, VarDeclKind, VarDeclarator,
+  ArrayLit, ArrowExpr, AssignTarget, BlockStmt, BlockStmtOrExpr, CallExpr, Callee, Class,
+  ClassDecl, ClassExpr, ClassMethod, Decl, DefaultDecl, EsVersion, ExportDefaultDecl,
+  ExportDefaultExpr, ExportNamedSpecifier, ExportSpecifier, Expr, ExprOrSpread, ExprStmt, FnExpr,
+  Function, GetterProp, Ident, ImportDecl, ImportSpecifier, JSXAttrName, JSXAttrOrSpread,
+  JSXAttrValue, JSXElement, JSXElementName, JSXExpr, JSXExprContainer, JSXMemberExpr, JSXObject,
+  KeyValueProp, Lit, MemberExpr, MemberProp, MethodKind, Module, ModuleDecl, ModuleExportName,
+  ModuleItem, NamedExport, NewExpr, ObjectLit, ParenExpr, Pat, Prop, PropName, PropOrSpread,
+  ReturnStmt, Script, SetterProp, SimpleAssignTarget, Stmt, TaggedTpl, Tpl, VarDecl, VarDeclKind,
+  VarDeclarator, YieldExpr,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VarType {
   JSX,
   AsyncJSX,
+  // `async function*` components, every `yield`ed chunk is streamed on its own
+  StreamJSX,
   Other,
   AsyncOther,
 }
@@ -27,62 +40,183 @@ pub enum VarType {
 impl VarType {
   pub fn is_async(self) -> bool {
     match self {
-      VarType::AsyncJSX | VarType::AsyncOther => true,
+      VarType::AsyncJSX | VarType::StreamJSX | VarType::AsyncOther => true,
       VarType::JSX | VarType::Other => false,
     }
   }
 
   fn awaited(self) -> VarType {
-    return match self {
+    match self {
       VarType::JSX => VarType::AsyncJSX,
       VarType::Other => VarType::AsyncOther,
       _ => self,
-    };
+    }
   }
 
   // Biggest -> Lowest
-  // Awaited JSX -> JSX -> Awaited Other -> Other
+  // Streamed JSX -> Awaited JSX -> JSX -> Awaited Other -> Other
   fn priority(self) -> u8 {
-    return match self {
+    match self {
+      VarType::StreamJSX => 4,
       VarType::AsyncJSX => 3,
       VarType::JSX => 2,
       VarType::AsyncOther => 1,
       VarType::Other => 0,
-    };
+    }
   }
 
   fn gt(self, other: VarType) -> VarType {
     if self.priority() > other.priority() {
       return self;
     }
-    return other;
+    other
   }
 }
 
 type IsVariableJsxMap = HashMap<String, VarType>;
+type ComponentKindMap = HashMap<String, ComponentKind>;
+
+// How a component gets rendered, besides what its `VarType` says
+#[derive(Debug, Clone)]
+pub enum ComponentKind {
+  // Called as `X(props)`
+  Function,
+  // Called as `new X(props).render()`
+  Class,
+  // Takes no props and always renders the same html, so it's inlined
+  Static(String),
+}
 
-pub struct TranspileVisitor<'a> {
-  #[allow(unused)]
-  pub compiler: &'a swc::Compiler,
-
-  return_type: VarType,
+pub struct TranspileVisitor {
+  // One entry per function we're currently inside of, aggregated over every
+  // `return` of that function. `None` until the first `return` is seen
+  return_types: Vec<Option<VarType>>,
+  // Same, over every `yield`
+  yield_types: Vec<Option<VarType>>,
   pub later_create_ident: Ident,
+  // Unique per rendered JSX expression, so a boundary in a loop still gets its own id
+  pub boundary_id_prefix_ident: Ident,
 
   pub function_variable_types: Vec<IsVariableJsxMap>,
+  // Same scoping as `function_variable_types`
+  component_kinds: Vec<ComponentKindMap>,
   last_arrow_function_return_type: VarType,
+  last_function_return_type: VarType,
+  // The html of the last visited function, if it's a static component
+  last_arrow_function_static_html: Option<String>,
+  last_function_static_html: Option<String>,
+  // The html of the last transformed JSX element, if it had no expressions
+  last_static_jsx: Option<String>,
+  // One entry per class we're currently inside of, the return type of its
+  // `render()` method, if it has one
+  class_render_types: Vec<Option<VarType>>,
+  last_class_render_type: Option<VarType>,
+
+  pub options: VisitorOptions,
+  // The output can't use anything newer, set by the compiler from its own target
+  pub target: EsVersion,
+
+  hoisted: RefCell<HoistedStrings>,
+  // Rules from `css` tagged templates and atomic styles
+  stylesheet: RefCell<Stylesheet>,
+
+  // For the manifest, every component declared and every component rendered.
+  // `component_names` are the declarations we're currently inside of
+  components: Vec<ComponentInfo>,
+  component_names: Vec<String>,
+  renders: RefCell<Vec<Render>>,
+  // Local name => exported name
+  exports: HashMap<String, String>,
 }
 
-impl TranspileVisitor<'_> {
-  pub fn new(compiler: &'_ swc::Compiler) -> TranspileVisitor {
-    return TranspileVisitor {
-      compiler,
+impl Default for TranspileVisitor {
+  fn default() -> Self {
+    TranspileVisitor::new()
+  }
+}
+
+impl TranspileVisitor {
+  pub fn with_options(options: VisitorOptions) -> TranspileVisitor {
+    let mut visitor = TranspileVisitor::new();
+    visitor.options = options;
+    visitor
+  }
+
+  // What a JSX attribute is called in the html, if it's renamed
+  pub fn attribute_name(&self, name: &str) -> Option<String> {
+    if let Some(name) = self.options.attributes.get(name) {
+      return Some(name.clone());
+    }
+    PROP_NAME_MAP.get(name).map(|name| name.to_string())
+  }
 
-      return_type: VarType::Other,
+  pub fn new() -> TranspileVisitor {
+    TranspileVisitor {
+      return_types: vec![],
+      yield_types: vec![],
       later_create_ident: utils::generate_random_variable_name(16).as_str().into(),
+      boundary_id_prefix_ident: utils::generate_random_variable_name(16).as_str().into(),
 
       function_variable_types: vec![IsVariableJsxMap::new()],
+      component_kinds: vec![ComponentKindMap::new()],
       last_arrow_function_return_type: VarType::Other,
-    };
+      last_function_return_type: VarType::Other,
+      last_arrow_function_static_html: None,
+      last_function_static_html: None,
+      last_static_jsx: None,
+      class_render_types: vec![],
+      last_class_render_type: None,
+
+      options: VisitorOptions::default(),
+      target: EsVersion::EsNext,
+      hoisted: RefCell::new(HoistedStrings::default()),
+      stylesheet: RefCell::new(Stylesheet::default()),
+
+      components: vec![],
+      component_names: vec![],
+      renders: RefCell::new(vec![]),
+      exports: HashMap::new(),
+    }
+  }
+
+  fn enter_function(&mut self) {
+    self.function_variable_types.push(IsVariableJsxMap::new());
+    self.component_kinds.push(ComponentKindMap::new());
+    self.return_types.push(None);
+    self.yield_types.push(None);
+  }
+
+  fn add_return_type(&mut self, return_type: VarType) {
+    if let Some(current) = self.return_types.last_mut() {
+      *current = Some(current.map_or(return_type, |c| c.gt(return_type)));
+    }
+  }
+
+  fn add_yield_type(&mut self, yield_type: VarType) {
+    if let Some(current) = self.yield_types.last_mut() {
+      *current = Some(current.map_or(yield_type, |c| c.gt(yield_type)));
+    }
+  }
+
+  fn exit_function(&mut self, is_async: bool, is_generator: bool) -> VarType {
+    self.function_variable_types.pop();
+    self.component_kinds.pop();
+
+    let return_type = self.return_types.pop().flatten().unwrap_or(VarType::Other);
+    let yield_type = self.yield_types.pop().flatten().unwrap_or(VarType::Other);
+    if is_generator {
+      // What a generator `return`s isn't rendered, only what it `yield`s.
+      // We only stream async ones, sync generators are just iterables
+      return match (is_async, yield_type) {
+        (true, VarType::JSX | VarType::AsyncJSX | VarType::StreamJSX) => VarType::StreamJSX,
+        (true, _) => VarType::AsyncOther,
+        (false, _) => VarType::Other,
+      };
+    }
+    if is_async {
+      return return_type.awaited();
+    }
+    return_type
   }
 
   fn is_ident_jsx<S: AsRef<str>>(&self, name: S) -> VarType {
@@ -93,12 +227,29 @@ impl TranspileVisitor<'_> {
       }
     }
 
-    return VarType::Other;
+    VarType::Other
+  }
+
+  fn get_component_kind(&self, expr: &Expr) -> ComponentKind {
+    match expr {
+      Expr::Class(_) if self.last_class_render_type.is_some() => ComponentKind::Class,
+      Expr::Fn(_) => self
+        .last_function_static_html
+        .clone()
+        .map_or(ComponentKind::Function, ComponentKind::Static),
+      Expr::Arrow(_) => self
+        .last_arrow_function_static_html
+        .clone()
+        .map_or(ComponentKind::Function, ComponentKind::Static),
+      Expr::Paren(paren) => self.get_component_kind(&paren.expr),
+      Expr::Ident(ident) => self.get_variable_kind(ident.sym.as_str()),
+      _ => ComponentKind::Function,
+    }
   }
 
   fn get_expr_type<E: AsRef<Expr>>(&self, to_assign_expr: E) -> VarType {
     match to_assign_expr.as_ref() {
-      Expr::JSXElement(_) | Expr::JSXFragment(_) => return VarType::JSX,
+      Expr::JSXElement(_) | Expr::JSXFragment(_) => VarType::JSX,
       Expr::Assign(assign) => self.get_expr_type(&assign.right),
       Expr::Await(expr) => self.get_expr_type(&expr.arg).awaited(),
       Expr::Call(call) => match &call.callee {
@@ -108,13 +259,13 @@ impl TranspileVisitor<'_> {
       Expr::Cond(cond) => self
         .get_expr_type(&cond.cons)
         .gt(self.get_expr_type(&cond.alt)),
-      // Expr::Fn() => Dont really know? Probably isnt JSX
-      // Expr::Fn(_) => self.last_arrow_function_is_jsx,
+      Expr::Fn(_) => self.last_function_return_type,
       Expr::Arrow(_) => self.last_arrow_function_return_type,
+      Expr::Class(_) => self.last_class_render_type.unwrap_or(VarType::Other),
       Expr::Ident(ident) => self.is_ident_jsx(ident),
       // Expr::Member() => TODO: Implement dis lol
       Expr::Paren(paren) => self.get_expr_type(&paren.expr),
-      _ => return VarType::Other,
+      _ => VarType::Other,
     }
   }
 
@@ -130,6 +281,456 @@ impl TranspileVisitor<'_> {
 
     None
   }
+
+  pub fn hoist_tpl(&self, tpl: Tpl) -> Expr {
+    if !self.options.hoist_static_templates {
+      return Expr::Tpl(tpl);
+    }
+    return self.hoisted.borrow_mut().hoist_tpl(tpl);
+  }
+
+  // Statements the transpiled code needs at the top of the module
+  fn take_module_stmts(&self) -> Vec<Stmt> {
+    let hoisted = self.hoisted.borrow_mut().take_decl();
+    let stylesheet = self.stylesheet.borrow_mut().take_registration();
+    hoisted.into_iter().chain(stylesheet).collect()
+  }
+
+  // `css\`color: red;\``, extracted into the page's stylesheet,
+  // and replaced with the class name it got
+  fn extract_css(&self, tagged: &TaggedTpl) -> Option<Expr> {
+    let Expr::Ident(tag) = &*tagged.tag else {
+      return None;
+    };
+    if tag.sym != "css" || self.get_variable_type("css").is_some() {
+      return None;
+    }
+
+    if let Some(expr) = tagged.tpl.exprs.first() {
+      utils::report_error(
+        expr.span(),
+        "`css` templates are extracted at compile time, and can't have expressions",
+      );
+      return None;
+    }
+
+    let declarations = tagged
+      .tpl
+      .quasis
+      .iter()
+      .map(|q| q.raw.as_str())
+      .collect::<String>();
+    let class_name = self.stylesheet.borrow_mut().add_rule(declarations);
+
+    Some(Expr::Lit(Lit::Str(class_name.into())))
+  }
+
+  // Takes the `style={{...}}` attribute out, and returns a class per
+  // declaration in it, if all of them are known at compile time
+  fn take_atomic_classes(&self, attrs: &mut Vec<JSXAttrOrSpread>) -> Option<String> {
+    let index = attrs.iter().position(|attr| match attr {
+      JSXAttrOrSpread::JSXAttr(attr) => {
+        matches!(&attr.name, JSXAttrName::Ident(name) if name.sym == "style")
+      }
+      JSXAttrOrSpread::SpreadElement(_) => false,
+    })?;
+
+    let JSXAttrOrSpread::JSXAttr(attr) = &attrs[index] else {
+      unreachable!()
+    };
+    let Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+      expr: JSXExpr::Expr(expr),
+      ..
+    })) = &attr.value
+    else {
+      return None;
+    };
+    let Expr::Object(obj) = &**expr else {
+      return None;
+    };
+
+    let declarations = utils::static_style_declarations(obj)?;
+    // They're escaped for an attribute, which doesn't work inside of a `<style>`
+    if declarations.iter().any(|d| d.contains('&')) {
+      return None;
+    }
+
+    attrs.remove(index);
+
+    let mut stylesheet = self.stylesheet.borrow_mut();
+    // Rules apply in stylesheet order, not class order, so a shorthand and its
+    // longhands only keep their declaration order if they stay in one rule
+    if utils::has_overlapping_declarations(&declarations) {
+      return Some(stylesheet.add_rule(declarations.join(";")));
+    }
+
+    let classes = declarations
+      .iter()
+      .map(|declaration| stylesheet.add_rule(declaration))
+      .collect::<Vec<_>>();
+
+    Some(classes.join(" "))
+  }
+
+  // Where `to` is rendered, and how
+  pub fn record_render(&self, to: String, member: bool, boundary: Boundary) {
+    self.renders.borrow_mut().push(Render {
+      from: self.component_names.last().cloned(),
+      to,
+      boundary,
+      member,
+    });
+  }
+
+  // Everything found in the module, once it's visited
+  pub fn take_components(&mut self) -> (Vec<ComponentInfo>, Vec<Render>) {
+    let renders = self.renders.take();
+    let mut components = std::mem::take(&mut self.components);
+    for component in components.iter_mut() {
+      component.async_children = renders
+        .iter()
+        .filter(|r| r.boundary != Boundary::Sync && r.from.as_ref() == Some(&component.name))
+        .map(|r| r.to.clone())
+        .collect();
+    }
+    (components, renders)
+  }
+
+  // `[html, (controller) => ...]`, and the html if it's all static
+  pub fn render_jsx(&self, jsx_element: Box<JSXElement>) -> (Expr, Option<String>) {
+    let mut created = ToCreateAsync::with_capacity(8);
+
+    let first = match utils::process_jsx_element(self, jsx_element, &mut created) {
+      utils::Processed::Async(div) => div,
+      utils::Processed::Sync(transformed) => transformed,
+    };
+
+    // The page's `<style>` goes in once the whole document is rendered, in the
+    // element with its `<body>`, since the `<head>` can be in a component of its own
+    let is_document = matches!(&first, Expr::Tpl(tpl) if tpl.quasis.iter().any(|q| {
+      q.raw.contains("<body") || q.raw.contains("<html")
+    }));
+
+    let static_html = match &first {
+      Expr::Tpl(tpl) if tpl.exprs.is_empty() && created.is_empty() && !is_document => Some(
+        tpl
+          .quasis
+          .iter()
+          .map(|q| q.raw.as_str())
+          .collect::<String>(),
+      ),
+      _ => None,
+    };
+
+    let first = match first {
+      Expr::Tpl(tpl) => self.hoist_tpl(tpl),
+      first => first,
+    };
+
+    let controller_name: Ident = utils::generate_random_variable_name(12).as_str().into();
+    let html_name: Ident = utils::generate_random_variable_name(12).as_str().into();
+
+    let array_name: Ident = utils::generate_random_variable_name(12).as_str().into();
+
+    let push = |expr: Expr| {
+      Stmt::Expr(ExprStmt {
+        span: Span::default(),
+        expr: Box::new(Expr::Call(CallExpr {
+          callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            obj: Box::new(Expr::Ident(array_name.clone())),
+            prop: MemberProp::Ident("push".into()),
+            ..MemberExpr::dummy()
+          }))),
+          args: vec![Box::new(expr).into()],
+          ..CallExpr::dummy()
+        })),
+      })
+    };
+
+    sort_parents_first(&mut created);
+    let has_boundaries = !created.is_empty();
+
+    // Resolves once the boundary's html has replaced its placeholder
+    let placed_idents: HashMap<ID, Ident> = created
+      .iter()
+      .map(|b| {
+        let ident = utils::generate_random_variable_name(12).as_str().into();
+        (b.id.clone(), ident)
+      })
+      .collect();
+
+    let f = |boundary: AsyncBoundary| {
+      let (fn_name, expr) = match boundary.later {
+        Later::Await(expr) => ("___FRAMEWORK_JS_ASYNC___", expr),
+        Later::Stream(expr) => match self.options.streaming {
+          Streaming::Chunks => ("___FRAMEWORK_JS_STREAM___", expr),
+          // Placed like an async component, once all of it is rendered
+          Streaming::Buffered => (
+            "___FRAMEWORK_JS_ASYNC___",
+            utils::call_framework_fn(
+              "___FRAMEWORK_JS_COLLECT___",
+              vec![
+                Box::new(expr).into(),
+                Box::new(Expr::Ident(self.later_create_ident.clone())).into(),
+              ],
+            ),
+          ),
+        },
+      };
+
+      // The props are rendered with their own `later_create_ident`, so the
+      // sync components in them are only created once we've been placed
+      let render = Expr::Arrow(ArrowExpr {
+        params: vec![Pat::Ident(self.later_create_ident.clone().into())],
+        body: Box::new(expr.into()),
+        ..ArrowExpr::dummy()
+      });
+
+      let mut args: Vec<ExprOrSpread> = vec![
+        Box::new(utils::boundary_id(self, &boundary.id)).into(),
+        Box::new(render).into(),
+        Box::new(Expr::Ident(controller_name.clone())).into(),
+      ];
+      let parent = match &boundary.parent {
+        Some(parent) => Expr::Ident(placed_idents[parent].clone()),
+        None => Expr::Ident("undefined".into()),
+      };
+      if boundary.parent.is_some() || boundary.fallback.is_some() {
+        args.push(Box::new(parent).into());
+      }
+      if let Some(fallback) = boundary.fallback {
+        args.push(Box::new(fallback).into());
+      }
+
+      let placed = placed_idents[&boundary.id].clone();
+      let rest: Ident = utils::generate_random_variable_name(12).as_str().into();
+
+      [
+        Stmt::Decl(Decl::Var(Box::new(VarDecl {
+          kind: VarDeclKind::Const,
+          decls: vec![VarDeclarator {
+            name: Pat::Ident(placed.clone().into()),
+            init: Some(Box::new(utils::call_framework_fn(fn_name, args))),
+            ..VarDeclarator::dummy()
+          }],
+          ..VarDecl::dummy()
+        }))),
+        // placed.then((rest) => rest())
+        push(Expr::Call(CallExpr {
+          callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            obj: Box::new(Expr::Ident(placed)),
+            prop: MemberProp::Ident("then".into()),
+            ..MemberExpr::dummy()
+          }))),
+          args: vec![Box::new(Expr::Arrow(ArrowExpr {
+            params: vec![Pat::Ident(rest.clone().into())],
+            body: Box::new(
+              Expr::Call(CallExpr {
+                callee: Callee::Expr(Box::new(Expr::Ident(rest))),
+                ..CallExpr::dummy()
+              })
+              .into(),
+            ),
+            ..ArrowExpr::dummy()
+          }))
+          .into()],
+          ..CallExpr::dummy()
+        })),
+      ]
+    };
+
+    let call = Expr::Arrow(ArrowExpr {
+      body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
+        stmts: {
+          let map_var: Ident = utils::generate_random_variable_name(12).as_str().into();
+          let mut stmts: Vec<Stmt> = created.into_iter().flat_map(f).collect();
+
+          stmts.insert(
+            0,
+            Stmt::Decl(Decl::Var(Box::new(VarDecl {
+              kind: VarDeclKind::Const,
+              decls: vec![VarDeclarator {
+                name: Pat::Ident(array_name.clone().into()),
+                init: Some(Box::new(Expr::Call(CallExpr {
+                  callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                    obj: Box::new(Expr::Ident(self.later_create_ident.clone())),
+                    prop: MemberProp::Ident("map".into()),
+                    ..MemberExpr::dummy()
+                  }))),
+                  args: vec![Box::new(Expr::Arrow(ArrowExpr {
+                    params: vec![Pat::Ident(map_var.clone().into())],
+                    body: Box::new(
+                      Expr::Call(CallExpr {
+                        callee: Callee::Expr(Expr::Ident(map_var).into()),
+                        args: vec![Box::new(Expr::Ident(controller_name.clone())).into()],
+                        ..CallExpr::dummy()
+                      })
+                      .into(),
+                    ),
+                    ..ArrowExpr::dummy()
+                  }))
+                  .into()],
+                  ..CallExpr::dummy()
+                }))),
+                ..VarDeclarator::dummy()
+              }],
+              ..VarDecl::dummy()
+            }))),
+          );
+
+          if is_document {
+            stmts.insert(
+              0,
+              Stmt::Expr(ExprStmt {
+                span: Span::default(),
+                expr: Box::new(utils::call_framework_fn(
+                  "___FRAMEWORK_JS_STYLESHEET_SENT___",
+                  vec![
+                    Box::new(Expr::Ident(controller_name.clone())).into(),
+                    Box::new(Expr::Ident(html_name.clone())).into(),
+                  ],
+                )),
+              }),
+            );
+          }
+
+          let args = vec![Box::new(Expr::Ident(array_name.clone())).into()];
+          stmts.push(Stmt::Return(ReturnStmt {
+            span: Span::dummy(),
+            arg: Some(Box::new(match self.target >= EsVersion::Es2020 {
+              true => Expr::Call(CallExpr {
+                callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                  obj: Box::new(Expr::Ident("Promise".into())),
+                  prop: MemberProp::Ident("allSettled".into()),
+                  ..MemberExpr::dummy()
+                }))),
+                args,
+                ..CallExpr::dummy()
+              }),
+              // Older runtimes don't have `Promise.allSettled`
+              false => utils::call_framework_fn("___FRAMEWORK_JS_ALL_SETTLED___", args),
+            })),
+          }));
+
+          stmts
+        },
+        ..BlockStmt::dummy()
+      })),
+      params: vec![Pat::Ident(controller_name.into())],
+      ..ArrowExpr::dummy()
+    });
+
+    let mut declarators = vec![VarDeclarator {
+      name: Pat::Ident(self.later_create_ident.clone().into()),
+      init: Some(Box::new(Expr::Array(ArrayLit::dummy()))),
+      ..VarDeclarator::dummy()
+    }];
+    if has_boundaries {
+      declarators.push(VarDeclarator {
+        name: Pat::Ident(self.boundary_id_prefix_ident.clone().into()),
+        init: Some(Box::new(utils::call_framework_fn(
+          "___FRAMEWORK_JS_ID___",
+          vec![],
+        ))),
+        ..VarDeclarator::dummy()
+      });
+    }
+    let first = match is_document {
+      true => {
+        declarators.push(VarDeclarator {
+          name: Pat::Ident(html_name.clone().into()),
+          init: Some(Box::new(utils::call_framework_fn(
+            "___FRAMEWORK_JS_STYLESHEET___",
+            vec![Box::new(first).into()],
+          ))),
+          ..VarDeclarator::dummy()
+        });
+        Expr::Ident(html_name)
+      }
+      false => first,
+    };
+
+    let rendered = Expr::Call(CallExpr {
+      callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
+        expr: Box::new(Expr::Arrow(ArrowExpr {
+          body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
+            stmts: vec![
+              Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                kind: VarDeclKind::Const,
+                declare: false,
+                decls: declarators,
+                ..VarDecl::dummy()
+              }))),
+              Stmt::Return(ReturnStmt {
+                span: Span::dummy(),
+                arg: Some(Box::new(Expr::Array(ArrayLit {
+                  elems: vec![
+                    Some(ExprOrSpread::from(first)),
+                    Some(ExprOrSpread::from(call)),
+                  ],
+                  ..ArrayLit::dummy()
+                }))),
+              }),
+            ],
+            ..BlockStmt::dummy()
+          })),
+          ..ArrowExpr::dummy()
+        })),
+        ..ParenExpr::dummy()
+      }))),
+      ..CallExpr::dummy()
+    });
+
+    (rendered, static_html)
+  }
+
+  // A declaration is a component if it renders JSX
+  fn record_component<N>(&mut self, name: &str, var_type: VarType, kind: &ComponentKind, node: &N)
+  where
+    N: VisitWith<manifest::HelperFinder>,
+  {
+    if !matches!(
+      var_type,
+      VarType::JSX | VarType::AsyncJSX | VarType::StreamJSX
+    ) {
+      return;
+    }
+
+    self.components.push(ComponentInfo {
+      name: name.to_owned(),
+      export: self.exports.get(name).cloned(),
+      var_type,
+      kind: match kind {
+        ComponentKind::Function => ComponentInfoKind::Function,
+        ComponentKind::Class => ComponentInfoKind::Class,
+        ComponentKind::Static(_) => ComponentInfoKind::Static,
+      },
+      async_children: vec![],
+      runtime_helpers: manifest::runtime_helpers(node),
+    });
+  }
+
+  fn declare_variable(&mut self, name: &Ident, var_type: VarType, kind: ComponentKind) {
+    let name = name.sym.as_str().to_owned();
+    if let Some(last) = self.component_kinds.last_mut() {
+      last.insert(name.clone(), kind);
+    }
+    if let Some(last) = self.function_variable_types.last_mut() {
+      last.insert(name, var_type);
+    }
+  }
+
+  pub fn get_variable_kind<S: AsRef<str>>(&self, name: S) -> ComponentKind {
+    let name = name.as_ref();
+
+    for map in self.component_kinds.iter().rev() {
+      if let Some(kind) = map.get(name) {
+        return kind.clone();
+      }
+    }
+
+    ComponentKind::Function
+  }
 }
 
 static PROP_NAME_MAP: phf::Map<&'static str, &'static str> = phf_map! {
@@ -167,7 +768,47 @@ pub enum ComponentType {
 }
 
 pub type ID = String;
-pub type ToCreateAsync = Vec<(ID, Expr)>;
+pub type ToCreateAsync = Vec<AsyncBoundary>;
+
+#[derive(Debug)]
+pub enum Later {
+  // Awaited once, and the `[html, later]` it resolves to replaces the placeholder
+  Await(Expr),
+  // An async iterator, every chunk is inserted before the placeholder as it arrives
+  Stream(Expr),
+}
+
+// An async component that is rendered as a `<div id>` placeholder first,
+// and streamed in place of it once it resolves
+#[derive(Debug)]
+pub struct AsyncBoundary {
+  pub id: ID,
+  // The boundary whose markup holds our placeholder, we can't be placed before it is
+  pub parent: Option<ID>,
+  pub later: Later,
+  // `() => fallback` of the closest `<ErrorBoundary>`, streamed in place of
+  // the placeholder if we reject
+  pub fallback: Option<Expr>,
+}
+
+// Parents have to be created before their children, since the children
+// wait on the parent's placeholder being replaced
+fn sort_parents_first(created: &mut ToCreateAsync) {
+  let parents: HashMap<ID, Option<ID>> = created
+    .iter()
+    .map(|b| (b.id.clone(), b.parent.clone()))
+    .collect();
+
+  created.sort_by_cached_key(|b| {
+    let mut depth = 0;
+    let mut parent = &b.parent;
+    while let Some(id) = parent {
+      depth += 1;
+      parent = &parents[id];
+    }
+    depth
+  });
+}
 pub type TransfromedJSX = (Expr, ComponentType);
 
 fn jsx_member_expr_to_member_expr(expr: JSXMemberExpr) -> MemberExpr {
@@ -175,11 +816,131 @@ fn jsx_member_expr_to_member_expr(expr: JSXMemberExpr) -> MemberExpr {
     JSXObject::Ident(i) => Expr::Ident(i),
     JSXObject::JSXMemberExpr(expr) => Expr::Member(jsx_member_expr_to_member_expr(*expr)),
   };
-  return MemberExpr {
+  MemberExpr {
     span: Span::dummy(),
     obj: Box::new(obj),
     prop: MemberProp::Ident(expr.prop),
+  }
+}
+
+fn unwrap_parens(expr: &Expr) -> &Expr {
+  match expr {
+    Expr::Paren(paren) => unwrap_parens(&paren.expr),
+    _ => expr,
+  }
+}
+
+// Local name => exported name, for everything the module exports itself
+fn module_exports(module: &Module) -> HashMap<String, String> {
+  let mut exports = HashMap::new();
+  // The first one it's exported as, when there's more than one
+  let mut add = |local: String, exported: String| {
+    exports.entry(local).or_insert(exported);
+  };
+  for item in &module.body {
+    let ModuleItem::ModuleDecl(decl) = item else {
+      continue;
+    };
+    match decl {
+      ModuleDecl::ExportDecl(export) => match &export.decl {
+        Decl::Fn(f) => add(f.ident.sym.to_string(), f.ident.sym.to_string()),
+        Decl::Class(c) => add(c.ident.sym.to_string(), c.ident.sym.to_string()),
+        Decl::Var(var) => {
+          for declarator in &var.decls {
+            if let Pat::Ident(i) = &declarator.name {
+              add(i.id.sym.to_string(), i.id.sym.to_string());
+            }
+          }
+        }
+        _ => {}
+      },
+      // `export { A, B as C }`, not re-exports
+      ModuleDecl::ExportNamed(NamedExport {
+        specifiers,
+        src: None,
+        ..
+      }) => {
+        for specifier in specifiers {
+          if let ExportSpecifier::Named(ExportNamedSpecifier {
+            orig: ModuleExportName::Ident(orig),
+            exported,
+            ..
+          }) = specifier
+          {
+            let exported = match exported {
+              Some(ModuleExportName::Ident(i)) => i.sym.to_string(),
+              Some(ModuleExportName::Str(s)) => s.value.to_string(),
+              None => orig.sym.to_string(),
+            };
+            add(orig.sym.to_string(), exported);
+          }
+        }
+      }
+      ModuleDecl::ExportDefaultExpr(export) => {
+        if let Expr::Ident(i) = unwrap_parens(&export.expr) {
+          add(i.sym.to_string(), "default".to_owned());
+        }
+      }
+      _ => {}
+    }
+  }
+  exports
+}
+
+fn is_jsx_element(expr: &Expr) -> bool {
+  match expr {
+    Expr::JSXElement(_) => true,
+    Expr::Paren(paren) => is_jsx_element(&paren.expr),
+    _ => false,
+  }
+}
+
+// `{ return <jsx />; }`, a candidate for a static component.
+// Whether it really is one is only known once the JSX is transformed
+fn returns_only_jsx(block: &BlockStmt) -> bool {
+  match block.stmts.as_slice() {
+    [Stmt::Return(ReturnStmt { arg: Some(arg), .. })] => is_jsx_element(arg),
+    _ => false,
+  }
+}
+
+// `() => fallback`, from the `fallback` prop of an `<ErrorBoundary>`
+fn error_boundary_fallback(v: &TranspileVisitor, attrs: Vec<JSXAttrOrSpread>) -> Option<Expr> {
+  let fallback = attrs.into_iter().find_map(|attr| match attr {
+    JSXAttrOrSpread::JSXAttr(attr) => match &attr.name {
+      swc_ecma_ast::JSXAttrName::Ident(i) if i.sym == "fallback" => attr.value,
+      _ => None,
+    },
+    JSXAttrOrSpread::SpreadElement(spread) => {
+      utils::report_error(
+        spread.span(),
+        "Props can't be spread into an `<ErrorBoundary>`, pass `fallback` directly",
+      );
+      None
+    }
+  })?;
+
+  let fallback = match fallback {
+    JSXAttrValue::Lit(lit) => Box::new(Expr::Lit(lit)),
+    JSXAttrValue::JSXExprContainer(container) => match container.expr {
+      JSXExpr::JSXEmptyExpr(_) => return None,
+      JSXExpr::Expr(expr) => expr,
+    },
+    // Rendered on its own, its boundaries are only created if it's streamed
+    JSXAttrValue::JSXElement(element) => Box::new(v.render_jsx(element).0),
+    JSXAttrValue::JSXFragment(fragment) => {
+      utils::report_error(
+        fragment.span,
+        "The `fallback` of an `<ErrorBoundary>` can't be a fragment, wrap it in an element",
+      );
+      return None;
+    }
   };
+
+  Some(Expr::Arrow(ArrowExpr {
+    body: Box::new(BlockStmtOrExpr::Expr(fallback)),
+    ..ArrowExpr::dummy()
+  }))
 }
 
 // We return the main Expr -> ie the TPL
@@ -189,7 +950,6 @@ pub fn transform(
   jsx_element: Box<JSXElement>,
   to_create: &mut ToCreateAsync,
 ) -> TransfromedJSX {
-  let compiler = &v.compiler;
   let opening = jsx_element.opening;
   let name = opening.name;
 
@@ -210,20 +970,55 @@ pub fn transform(
     JSXElementName::JSXNamespacedName(_) => None,
   };
 
+  let has_children = !jsx_element.children.is_empty();
+  let first_child = to_create.len();
   let mut children = TplWrapper::new();
   for element in jsx_element.children {
     children.append_element_child(v, element, to_create);
   }
 
+  if let Some(CustomComponent::Ident(ident)) = &custom_name {
+    // Unless it's shadowed by a component or an import of the same name
+    if ident.sym == "ErrorBoundary" && v.get_variable_type("ErrorBoundary").is_none() {
+      let fallback = error_boundary_fallback(v, opening.attrs);
+
+      for boundary in to_create[first_child..].iter_mut() {
+        if boundary.fallback.is_none() {
+          boundary.fallback = fallback.clone();
+        }
+      }
+
+      // It doesn't render anything by itself, only its children
+      return (Expr::Tpl(children.build()), ComponentType::HTML);
+    }
+  }
+
   if let Some(custom_name) = custom_name {
-    let expr = if children.exprs.len() == 0 {
+    let kind = match &custom_name {
+      CustomComponent::Ident(i) => v.get_variable_kind(i.sym.as_str()),
+      CustomComponent::Member(_) => ComponentKind::Function,
+    };
+
+    if let ComponentKind::Static(html) = &kind {
+      if opening.attrs.is_empty() && !has_children {
+        v.record_render(custom_name.stringify(), false, Boundary::Sync);
+        let mut tpl = TplWrapper::new();
+        tpl.append_quasi(html);
+        return (Expr::Tpl(tpl.build()), ComponentType::HTML);
+      }
+    }
+
+    let expr = if children.exprs.is_empty() {
       let html = children
         .quasis
         .pop()
         .map_or(String::new(), |q| q.raw.as_str().to_owned());
-      Expr::Lit(Lit::Str(html.into()))
+      match html.len() < HOIST_MIN_LENGTH {
+        true => Expr::Lit(Lit::Str(html.into())),
+        false => v.hoist_tpl(TplWrapper::from_quasi(html).build()),
+      }
     } else {
-      Expr::Tpl(children.build())
+      v.hoist_tpl(children.build())
     };
 
     let children_prop = PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
@@ -244,15 +1039,21 @@ pub fn transform(
 
       let prop_name = match attr.name {
         swc_ecma_ast::JSXAttrName::Ident(ident) => ident,
-        _ => {
-          unimplemented!("Other types of attribute names are not implemented for prop propagation!")
+        JSXAttrName::JSXNamespacedName(name) => {
+          utils::report_error(
+            name.span(),
+            "Namespaced props like `a:b` can only be passed to html elements",
+          );
+          continue;
         }
       };
 
-      let key = PropName::Ident(match PROP_NAME_MAP.get(prop_name.sym.as_str()) {
-        Some(name) => name.to_string().as_str().into(),
-        None => prop_name,
-      });
+      let key = match v.attribute_name(&prop_name.sym) {
+        // Mappings from the config don't have to be valid identifiers
+        Some(name) if Ident::verify_symbol(&name).is_err() => PropName::Str(name.into()),
+        Some(name) => PropName::Ident(Ident::new(name.into(), prop_name.span)),
+        None => PropName::Ident(prop_name),
+      };
 
       let value = match attr.value {
         None => Box::new(Expr::Lit(Lit::Bool(true.into()))),
@@ -262,12 +1063,10 @@ pub fn transform(
             JSXExpr::JSXEmptyExpr(_) => Box::new(Expr::Lit(Lit::Bool(true.into()))),
             JSXExpr::Expr(expr) => expr,
           },
-          JSXAttrValue::JSXElement(el) => {
-            match utils::process_transformed_jsx(transform(v, el, to_create), v, to_create) {
-              utils::Processed::Async(div) => Box::new(Expr::Lit(Lit::Str(div.into()))),
-              utils::Processed::Sync(transformed) => Box::new(transformed),
-            }
-          }
+          JSXAttrValue::JSXElement(el) => match utils::process_jsx_element(v, el, to_create) {
+            utils::Processed::Async(div) => Box::new(div),
+            utils::Processed::Sync(transformed) => Box::new(transformed),
+          },
           JSXAttrValue::JSXFragment(frag) => {
             let mut children = TplWrapper::new();
             for child in frag.children {
@@ -292,24 +1091,80 @@ pub fn transform(
       })),
     };
 
-    let call = CallExpr {
-      callee: Callee::Expr(Box::new(custom_name.expr())),
-      args: vec![expr],
-      ..CallExpr::dummy()
+    let call = match kind {
+      // (p => Object.assign(new Component(p), { props: p }).render())(props),
+      // so `this.props` is set without a constructor that does it
+      ComponentKind::Class => {
+        let param: Ident = utils::generate_random_variable_name(12).as_str().into();
+        let instance = Expr::Call(CallExpr {
+          callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            obj: Box::new(Expr::Ident("Object".into())),
+            prop: MemberProp::Ident("assign".into()),
+            ..MemberExpr::dummy()
+          }))),
+          args: vec![
+            Expr::New(NewExpr {
+              callee: Box::new(custom_name.expr()),
+              args: Some(vec![Expr::Ident(param.clone()).into()]),
+              ..NewExpr::dummy()
+            })
+            .into(),
+            Expr::Object(ObjectLit {
+              props: vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident("props".into()),
+                value: Box::new(Expr::Ident(param.clone())),
+              })))],
+              ..ObjectLit::dummy()
+            })
+            .into(),
+          ],
+          ..CallExpr::dummy()
+        });
+        let render = Expr::Call(CallExpr {
+          callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            obj: Box::new(instance),
+            prop: MemberProp::Ident("render".into()),
+            ..MemberExpr::dummy()
+          }))),
+          ..CallExpr::dummy()
+        });
+        CallExpr {
+          callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
+            expr: Box::new(Expr::Arrow(ArrowExpr {
+              params: vec![Pat::Ident(param.into())],
+              body: Box::new(BlockStmtOrExpr::Expr(Box::new(render))),
+              ..ArrowExpr::dummy()
+            })),
+            ..ParenExpr::dummy()
+          }))),
+          args: vec![expr],
+          ..CallExpr::dummy()
+        }
+      }
+      ComponentKind::Function | ComponentKind::Static(_) => CallExpr {
+        callee: Callee::Expr(Box::new(custom_name.expr())),
+        args: vec![expr],
+        ..CallExpr::dummy()
+      },
     };
 
     return (Expr::Call(call), ComponentType::Custom(custom_name));
   }
 
+  let mut attrs = opening.attrs;
+  let mut atomic_classes = match v.options.atomic_styles {
+    true => v.take_atomic_classes(&mut attrs).filter(|c| !c.is_empty()),
+    false => None,
+  };
+
   let mut props = TplWrapper::new();
 
-  for attr in opening.attrs {
-    props.append_quasi(" ");
+  // Every attribute brings its own leading space, skipped ones add nothing
+  for attr in attrs {
     match attr {
       JSXAttrOrSpread::SpreadElement(spread) => {
-        props.append_quasi("${Object.entries(");
-        props.append_quasi(utils::expr_to_string(&compiler, &spread.expr));
-        props.append_quasi(").map(([key, value]) => `${key}=\"${value ? (typeof value === 'string' ? value : (value instanceof RegExp ? value.toString() : JSON.stringify(value))).replace(/\"/mg, '\\\\\"') : 'true'}\"`).join(' ')}");
+        props.append_quasi(" ");
+        props.append_expr(utils::spread_attributes(*spread.expr));
       }
       JSXAttrOrSpread::JSXAttr(attr) => {
         let prop_name = utils::stringify::stringify_jsx_attr_name(attr.name);
@@ -318,66 +1173,66 @@ pub fn transform(
           let Some(value) = attr.value else {
             continue;
           };
-          match value {
-            JSXAttrValue::JSXExprContainer(container) => {
-              let expr = match container.expr {
-                JSXExpr::Expr(expr) => expr,
-                JSXExpr::JSXEmptyExpr(empty) => {
-                  println!("No `value` expression for style \"{:#?}\"?", empty.span);
-                  continue;
-                }
-              };
-
-              match *expr {
-                Expr::Object(obj) => {
-                  props.append_quasi(format!("style=\""));
-                  props.append_expr(Expr::Tpl(utils::style_object_to_string(obj)));
-                  props.append_quasi(format!("\""));
-                }
-                Expr::Ident(_) => {
-                  props.append_quasi(format!("style=\""));
-                  props.append_expr(utils::call_framework_fn(
-                    "___FRAMEWORK_JS_STYLE_OBJECT___",
-                    vec![expr.into()],
-                  ));
-                  props.append_quasi(format!("\""));
-                }
-                _ => unimplemented!("Unknown `value` expr type {:#?}!", expr),
+          let style = match value {
+            JSXAttrValue::JSXExprContainer(container) => match container.expr {
+              JSXExpr::Expr(expr) => *expr,
+              JSXExpr::JSXEmptyExpr(empty) => {
+                utils::report_error(empty.span, "`style` needs a value, or can be left out");
+                continue;
               }
-
+            },
+            // `style="color: red"`
+            JSXAttrValue::Lit(lit) => Expr::Lit(lit),
+            value => {
+              utils::report_error(value.span(), "`style` has to be an object or a string");
               continue;
             }
-            _ => unreachable!(),
-          }
+          };
+
+          let Some(style) = utils::style_to_string(style) else {
+            continue;
+          };
+          props.append_quasi(" style=\"");
+          props.append_expr(style);
+          props.append_quasi("\"");
+
+          continue;
         }
 
-        let prop_name = match PROP_NAME_MAP.get(&prop_name) {
-          Some(name) => name.to_string(),
-          None => prop_name,
-        };
+        let prop_name = v.attribute_name(&prop_name).unwrap_or(prop_name);
 
-        props.append_quasi(format!("{prop_name}=\""));
+        props.append_quasi(format!(" {prop_name}=\""));
         match attr.value {
+          // A bare `class` has no class names of its own
+          None if prop_name == "class" && atomic_classes.is_some() => {
+            props.append_quasi(format!("{}\"", atomic_classes.take().unwrap()));
+          }
           None => props.append_quasi("true\""),
           Some(value) => {
             match value {
               JSXAttrValue::Lit(lit) => props.append_lit(lit),
               JSXAttrValue::JSXExprContainer(container) => match container.expr {
                 JSXExpr::JSXEmptyExpr(_) => props.append_quasi("true"),
+                JSXExpr::Expr(expr) if prop_name == "class" => {
+                  props.append_expr(utils::class_to_string(*expr))
+                }
                 JSXExpr::Expr(expr) => props.append_expr(*expr),
               },
-              JSXAttrValue::JSXElement(el) => {
-                match utils::process_transformed_jsx(transform(v, el, to_create), v, to_create) {
-                  utils::Processed::Async(div) => props.append_quasi(div),
-                  utils::Processed::Sync(transformed) => props.append_expr(transformed),
-                }
-              }
+              JSXAttrValue::JSXElement(el) => match utils::process_jsx_element(v, el, to_create) {
+                utils::Processed::Async(div) => props.append_expr(div),
+                utils::Processed::Sync(transformed) => props.append_expr(transformed),
+              },
               JSXAttrValue::JSXFragment(frag) => {
                 for child in frag.children {
                   props.append_element_child(v, child, to_create);
                 }
               }
             };
+            if prop_name == "class" {
+              if let Some(classes) = atomic_classes.take() {
+                props.append_quasi(format!(" {classes}"));
+              }
+            }
             props.append_quasi("\"");
           }
         }
@@ -385,319 +1240,967 @@ pub fn transform(
     }
   }
 
+  if let Some(classes) = atomic_classes {
+    props.append_quasi(format!(" class=\"{classes}\""));
+  }
+
   let name = utils::stringify::stringify_jsx_element_name(name);
 
   let mut shell = TplWrapper::new();
 
   shell.append_quasi(format!("<{name}"));
   shell.append_tpl(props);
-  shell.append_quasi(format!(">"));
+  shell.append_quasi(">");
   shell.append_tpl(children);
+  if name == "head" {
+    // Replaced with the rules the page uses, once all of it is rendered
+    shell.append_quasi(css::STYLESHEET_MARKER);
+  }
   shell.append_quasi(format!("</{name}>"));
 
   let expr_tpl = Expr::Tpl(shell.build());
-  return (expr_tpl, ComponentType::HTML);
+  (expr_tpl, ComponentType::HTML)
 }
 
-impl<'a> VisitMut for TranspileVisitor<'a> {
+impl VisitMut for TranspileVisitor {
   fn visit_mut_expr(&mut self, n: &mut Expr) {
     n.visit_mut_children_with(self);
 
+    if let Expr::TaggedTpl(tagged) = n {
+      if let Some(class_name) = self.extract_css(tagged) {
+        *n = class_name;
+      }
+      return;
+    }
+
     if let Expr::JSXElement(_) = n {
       n.map_with_mut(|n| {
         let Expr::JSXElement(jsx_element) = n else {
           unreachable!()
         };
-        let mut created = ToCreateAsync::with_capacity(8);
-
-        let transformed = transform(self, jsx_element, &mut created);
-
-        let first = match utils::process_transformed_jsx(transformed, &self, &mut created) {
-          utils::Processed::Async(div) => Expr::Lit(Lit::Str(div.into())),
-          utils::Processed::Sync(transformed) => transformed,
-        };
+        let (rendered, static_html) = self.render_jsx(jsx_element);
+        self.last_static_jsx = static_html;
+        rendered
+      });
+    }
+  }
 
-        let controller_name: Ident = utils::generate_random_variable_name(12).as_str().into();
-
-        let html_ident: Ident = utils::generate_random_variable_name(12).as_str().into();
-        let later_fn_ident: Ident = utils::generate_random_variable_name(12).as_str().into();
-
-        let array_name: Ident = utils::generate_random_variable_name(12).as_str().into();
-
-        let f = |(id, expr): (String, Expr)| {
-          let decl = Stmt::Decl(Decl::Var(Box::new(VarDecl {
-            kind: VarDeclKind::Const,
-            declare: false,
-            decls: vec![VarDeclarator {
-              name: Pat::Array(ArrayPat {
-                elems: vec![
-                  Some(Pat::Ident(html_ident.clone().into())),
-                  Some(Pat::Ident(later_fn_ident.clone().into())),
-                ],
-                optional: false,
-                type_ann: None,
-                span: Span::dummy(),
-              }),
-              init: Some(Box::new(expr)),
-              ..VarDeclarator::dummy()
-            }],
-            ..VarDecl::dummy()
-          })));
-
-          let script_id = utils::generate_random_variable_name(12);
-
-          let mut tpl = TplWrapper::new();
-          tpl.append_quasi(format!(
-            "<script id=\"{script_id}\">document.getElementById(\"{id}\").outerHTML = \\`"
-          ));
-
-          tpl.append_expr(Expr::Call(CallExpr {
-            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-              obj: Box::new(Expr::Ident(html_ident.clone().into())),
-              prop: MemberProp::Ident("replace".into()),
-              ..MemberExpr::dummy()
-            }))),
-            args: vec![
-              Box::new(Expr::Lit(Lit::Regex(Regex {
-                exp: "`".into(),
-                flags: "mg".into(),
-                ..Regex::dummy()
-              })))
-              .into(),
-              Box::new(Expr::Lit(Lit::Str("\\`".into()))).into(),
-            ],
-            ..CallExpr::dummy()
-          }));
+  fn visit_mut_module(&mut self, module: &mut Module) {
+    self.exports = module_exports(module);
+    module.visit_mut_children_with(self);
+
+    // After the imports, before anything that could render
+    let position = module
+      .body
+      .iter()
+      .position(|item| !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+      .unwrap_or(module.body.len());
+    module.body.splice(
+      position..position,
+      self.take_module_stmts().into_iter().map(ModuleItem::Stmt),
+    );
+
+    // The runtime has to be set up before any of the helpers get called
+    if let Some(source) = &self.options.runtime.import_source {
+      module.body.insert(
+        0,
+        ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+          src: Box::new(source.as_str().into()),
+          ..ImportDecl::dummy()
+        })),
+      );
+    }
 
-          tpl.append_quasi(format!(
-            "\\`;document.getElementById(\"{script_id}\").remove();</script>"
-          ));
-
-          let enqueue = Stmt::Expr(ExprStmt {
-            span: Span::default(),
-            expr: Box::new(Expr::Call(CallExpr {
-              callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-                obj: Box::new(controller_name.clone().into()),
-                prop: MemberProp::Ident("enqueue".into()),
-                ..MemberExpr::dummy()
-              }))),
-              args: vec![ExprOrSpread::from(Box::new(tpl.build().into()))],
-              ..CallExpr::dummy()
-            })),
-          });
-
-          let later_fn_call = Stmt::Return(ReturnStmt {
-            span: Span::default(),
-            arg: Some(Box::new(Expr::Call(CallExpr {
-              callee: Callee::Expr(Box::new(Expr::Ident(later_fn_ident.clone().into()))),
-              args: vec![ExprOrSpread::from(Box::new(controller_name.clone().into()))],
-              ..CallExpr::dummy()
-            }))),
-          });
-
-          Stmt::Expr(ExprStmt {
-            span: Span::default(),
-            expr: Box::new(Expr::Call(CallExpr {
-              callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-                obj: Box::new(Expr::Ident(array_name.clone())),
-                prop: MemberProp::Ident("push".into()),
-                ..MemberExpr::dummy()
-              }))),
-              args: vec![Box::new(Expr::Call(CallExpr {
-                callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
-                  expr: Box::new(Expr::Arrow(ArrowExpr {
-                    is_async: true,
-                    body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
-                      stmts: vec![decl, enqueue, later_fn_call],
-                      ..BlockStmt::dummy()
-                    })),
-                    ..ArrowExpr::dummy()
-                  })),
-                  ..ParenExpr::dummy()
-                }))),
-                ..CallExpr::dummy()
-              }))
-              .into()],
-              ..CallExpr::dummy()
-            })),
-          })
-        };
+    utils::rename_runtime_global(module, &self.options.runtime.global);
+  }
 
-        let call = Expr::Arrow(ArrowExpr {
-          body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
-            stmts: {
-              let map_var: Ident = utils::generate_random_variable_name(12).as_str().into();
-              let mut stmts: Vec<Stmt> = created.into_iter().map(f).collect();
-
-              stmts.insert(
-                0,
-                Stmt::Decl(Decl::Var(Box::new(VarDecl {
-                  kind: VarDeclKind::Const,
-                  decls: vec![VarDeclarator {
-                    name: Pat::Ident(array_name.clone().into()),
-                    init: Some(Box::new(Expr::Call(CallExpr {
-                      callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-                        obj: Box::new(Expr::Ident(self.later_create_ident.clone().into())),
-                        prop: MemberProp::Ident("map".into()),
-                        ..MemberExpr::dummy()
-                      }))),
-                      args: vec![Box::new(Expr::Arrow(ArrowExpr {
-                        params: vec![Pat::Ident(map_var.clone().into())],
-                        body: Box::new(
-                          Expr::Call(CallExpr {
-                            callee: Callee::Expr(Expr::Ident(map_var).into()),
-                            args: vec![Box::new(Expr::Ident(controller_name.clone())).into()],
-                            ..CallExpr::dummy()
-                          })
-                          .into(),
-                        ),
-                        ..ArrowExpr::dummy()
-                      }))
-                      .into()],
-                      ..CallExpr::dummy()
-                    }))),
-                    ..VarDeclarator::dummy()
-                  }],
-                  ..VarDecl::dummy()
-                }))),
-              );
+  fn visit_mut_import_decl(&mut self, import: &mut ImportDecl) {
+    // Nothing is known about what they are, but they shadow the built-ins
+    for specifier in &import.specifiers {
+      let local = match specifier {
+        ImportSpecifier::Named(named) => &named.local,
+        ImportSpecifier::Default(default) => &default.local,
+        ImportSpecifier::Namespace(namespace) => &namespace.local,
+      };
+      if let Some(module) = self.function_variable_types.first_mut() {
+        module.insert(local.sym.as_str().to_owned(), VarType::Other);
+      }
+    }
+  }
 
-              stmts.push(Stmt::Return(ReturnStmt {
-                span: Span::dummy(),
-                arg: Some(Box::new(Expr::Call(CallExpr {
-                  callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-                    obj: Box::new(Expr::Ident("Promise".into())),
-                    prop: MemberProp::Ident("allSettled".into()),
-                    ..MemberExpr::dummy()
-                  }))),
-                  args: vec![Box::new(Expr::Ident(array_name.clone())).into()],
-                  ..CallExpr::dummy()
-                }))),
-              }));
+  fn visit_mut_script(&mut self, script: &mut Script) {
+    script.visit_mut_children_with(self);
 
-              stmts
-            },
-            ..BlockStmt::dummy()
-          })),
-          params: vec![Pat::Ident(controller_name.into())],
-          ..ArrowExpr::dummy()
-        });
+    script.body.splice(0..0, self.take_module_stmts());
 
-        Expr::Call(CallExpr {
-          callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
-            expr: Box::new(Expr::Arrow(ArrowExpr {
-              body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
-                stmts: vec![
-                  Stmt::Decl(Decl::Var(Box::new(VarDecl {
-                    kind: VarDeclKind::Const,
-                    declare: false,
-                    decls: vec![VarDeclarator {
-                      name: Pat::Ident(self.later_create_ident.clone().into()),
-                      init: Some(Box::new(Expr::Array(ArrayLit::dummy()))),
-                      ..VarDeclarator::dummy()
-                    }],
-                    ..VarDecl::dummy()
-                  }))),
-                  Stmt::Return(ReturnStmt {
-                    span: Span::dummy(),
-                    arg: Some(Box::new(Expr::Array(ArrayLit {
-                      elems: vec![
-                        Some(ExprOrSpread::from(first)),
-                        Some(ExprOrSpread::from(call)),
-                      ],
-                      ..ArrayLit::dummy()
-                    }))),
-                  }),
-                ],
-                ..BlockStmt::dummy()
-              })),
-              ..ArrowExpr::dummy()
-            })),
-            ..ParenExpr::dummy()
-          }))),
-          ..CallExpr::dummy()
-        })
-      });
-    }
+    utils::rename_runtime_global(script, &self.options.runtime.global);
   }
 
   fn visit_mut_arrow_expr(&mut self, arrow: &mut swc_ecma_ast::ArrowExpr) {
-    self.function_variable_types.push(IsVariableJsxMap::new());
+    let can_be_static = arrow.params.is_empty()
+      && !arrow.is_async
+      && match &*arrow.body {
+        BlockStmtOrExpr::Expr(expr) => is_jsx_element(expr),
+        BlockStmtOrExpr::BlockStmt(block) => returns_only_jsx(block),
+      };
 
-    let return_type = if let Some(expr) = arrow.body.as_expr() {
-      Some(self.get_expr_type(expr))
-    } else {
-      None
-    };
+    self.enter_function();
 
-    arrow.visit_mut_children_with(self);
-
-    if let Some(return_type) = return_type {
-      self.return_type = return_type;
+    if let Some(expr) = arrow.body.as_expr() {
+      let return_type = self.get_expr_type(expr);
+      self.add_return_type(return_type);
     }
 
-    if arrow.is_async {
-      self.return_type = self.return_type.awaited()
-    };
-    self.last_arrow_function_return_type = self.return_type;
+    arrow.visit_mut_children_with(self);
 
-    self.function_variable_types.pop();
+    self.last_arrow_function_return_type = self.exit_function(arrow.is_async, false);
+    self.last_arrow_function_static_html = match can_be_static {
+      true => self.last_static_jsx.take(),
+      false => None,
+    };
   }
 
   fn visit_mut_assign_expr(&mut self, assign: &mut swc_ecma_ast::AssignExpr) {
     assign.visit_mut_children_with(self);
 
     let is_jsx = self.get_expr_type(&assign.right);
-    if let Some(last) = self.function_variable_types.last_mut() {
-      match &assign.left {
-        AssignTarget::Simple(simple) => match simple {
-          SimpleAssignTarget::Ident(ident) => {
-            last.insert(ident.id.sym.as_str().to_owned(), is_jsx);
-          }
-          _ => {}
-        },
-        _ => {}
-      }
+    let kind = self.get_component_kind(&assign.right);
+    if let AssignTarget::Simple(SimpleAssignTarget::Ident(ident)) = &assign.left {
+      self.declare_variable(&ident.id, is_jsx, kind);
     }
   }
 
   fn visit_mut_var_declarator(&mut self, declarator: &mut VarDeclarator) {
+    let component_name = match (
+      &declarator.name,
+      declarator.init.as_deref().map(unwrap_parens),
+    ) {
+      (Pat::Ident(i), Some(Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_))) => {
+        Some(i.id.sym.to_string())
+      }
+      _ => None,
+    };
+
+    self.component_names.extend(component_name.clone());
     declarator.visit_mut_children_with(self);
 
     if let Some(init) = &declarator.init {
       let is_jsx = self.get_expr_type(init);
-      if let Some(last) = self.function_variable_types.last_mut() {
-        match &declarator.name {
-          Pat::Ident(i) => {
-            last.insert(i.id.sym.as_str().to_owned(), is_jsx);
-          }
-          _ => {}
-        }
+      let kind = self.get_component_kind(init);
+      if let Some(name) = &component_name {
+        self.component_names.pop();
+        self.record_component(name, is_jsx, &kind, &**init);
+      }
+      if let Pat::Ident(i) = &declarator.name {
+        self.declare_variable(&i.id, is_jsx, kind);
       }
     }
   }
 
   fn visit_mut_return_stmt(&mut self, ret: &mut ReturnStmt) {
-    let Some(arg) = &ret.arg else {
-      ret.visit_mut_children_with(self);
-      return;
+    if let Some(arg) = &ret.arg {
+      let return_type = self.get_expr_type(arg);
+      self.add_return_type(return_type);
+    }
+
+    ret.visit_mut_children_with(self);
+  }
+
+  fn visit_mut_yield_expr(&mut self, yield_expr: &mut YieldExpr) {
+    if let Some(arg) = &yield_expr.arg {
+      let yield_type = self.get_expr_type(arg);
+      self.add_yield_type(yield_type);
+    }
+
+    yield_expr.visit_mut_children_with(self);
+  }
+
+  fn visit_mut_function(&mut self, function: &mut Function) {
+    let can_be_static = function.params.is_empty()
+      && !function.is_async
+      && !function.is_generator
+      && function.body.as_ref().is_some_and(returns_only_jsx);
+
+    self.enter_function();
+    function.visit_mut_children_with(self);
+    self.last_function_return_type = self.exit_function(function.is_async, function.is_generator);
+    self.last_function_static_html = match can_be_static {
+      true => self.last_static_jsx.take(),
+      false => None,
     };
+  }
 
-    self.return_type = self.get_expr_type(arg);
+  fn visit_mut_getter_prop(&mut self, getter: &mut GetterProp) {
+    self.enter_function();
+    getter.visit_mut_children_with(self);
+    self.exit_function(false, false);
+  }
 
-    ret.visit_mut_children_with(self);
+  fn visit_mut_setter_prop(&mut self, setter: &mut SetterProp) {
+    self.enter_function();
+    setter.visit_mut_children_with(self);
+    self.exit_function(false, false);
   }
 
   fn visit_mut_fn_decl(&mut self, decl: &mut swc_ecma_ast::FnDecl) {
-    self.function_variable_types.push(IsVariableJsxMap::new());
+    self.component_names.push(decl.ident.sym.to_string());
     decl.visit_mut_children_with(self);
-    self.function_variable_types.pop();
+    self.component_names.pop();
+
+    let kind = self
+      .last_function_static_html
+      .clone()
+      .map_or(ComponentKind::Function, ComponentKind::Static);
+    self.record_component(
+      &decl.ident.sym,
+      self.last_function_return_type,
+      &kind,
+      &*decl,
+    );
+    self.declare_variable(&decl.ident, self.last_function_return_type, kind);
+  }
 
-    if let Some(last) = self.function_variable_types.last_mut() {
-      if decl.function.is_async {
-        self.return_type = self.return_type.awaited()
-      };
-      last.insert(decl.ident.sym.as_str().to_owned(), self.return_type.clone());
+  // `export default function () {}`, and classes
+  fn visit_mut_export_default_decl(&mut self, export: &mut ExportDefaultDecl) {
+    let name = match &export.decl {
+      DefaultDecl::Fn(FnExpr { ident: Some(i), .. }) => i.sym.to_string(),
+      DefaultDecl::Class(ClassExpr { ident: Some(i), .. }) => i.sym.to_string(),
+      _ => "default".to_owned(),
+    };
+
+    self.component_names.push(name.clone());
+    export.visit_mut_children_with(self);
+    self.component_names.pop();
+
+    let (var_type, kind) = match &export.decl {
+      DefaultDecl::Fn(_) => (
+        self.last_function_return_type,
+        self
+          .last_function_static_html
+          .clone()
+          .map_or(ComponentKind::Function, ComponentKind::Static),
+      ),
+      DefaultDecl::Class(_) => (
+        self.last_class_render_type.unwrap_or(VarType::Other),
+        ComponentKind::Class,
+      ),
+      DefaultDecl::TsInterfaceDecl(_) => return,
+    };
+    self.exports.insert(name.clone(), "default".to_owned());
+    self.record_component(&name, var_type, &kind, &*export);
+  }
+
+  // `export default () => <jsx />`
+  fn visit_mut_export_default_expr(&mut self, export: &mut ExportDefaultExpr) {
+    let is_component = matches!(
+      unwrap_parens(&export.expr),
+      Expr::Fn(_) | Expr::Arrow(_) | Expr::Class(_)
+    );
+
+    self.component_names.push("default".to_owned());
+    export.visit_mut_children_with(self);
+    self.component_names.pop();
+
+    if is_component {
+      let var_type = self.get_expr_type(&export.expr);
+      let kind = self.get_component_kind(&export.expr);
+      self
+        .exports
+        .insert("default".to_owned(), "default".to_owned());
+      self.record_component("default", var_type, &kind, &*export.expr);
+    }
+  }
+
+  fn visit_mut_class(&mut self, class: &mut Class) {
+    self.class_render_types.push(None);
+    class.visit_mut_children_with(self);
+    self.last_class_render_type = self.class_render_types.pop().flatten();
+  }
+
+  fn visit_mut_class_method(&mut self, method: &mut ClassMethod) {
+    method.visit_mut_children_with(self);
+
+    let is_render = match &method.key {
+      PropName::Ident(i) => i.sym == "render",
+      PropName::Str(s) => s.value == "render",
+      _ => false,
+    };
+
+    if is_render && !method.is_static && method.kind == MethodKind::Method {
+      if let Some(render) = self.class_render_types.last_mut() {
+        *render = Some(self.last_function_return_type);
+      }
+    }
+  }
+
+  fn visit_mut_class_decl(&mut self, decl: &mut ClassDecl) {
+    self.component_names.push(decl.ident.sym.to_string());
+    decl.visit_mut_children_with(self);
+    self.component_names.pop();
+
+    if let Some(render) = self.last_class_render_type {
+      self.record_component(&decl.ident.sym, render, &ComponentKind::Class, &*decl);
+      self.declare_variable(&decl.ident, render, ComponentKind::Class);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Streaming, TranspileVisitor, VarType};
+  use std::{path::PathBuf, sync::Arc};
+  use swc::{try_with_handler, PrintArgs};
+  use swc_common::{SourceMap, GLOBALS};
+  use swc_core::ecma::visit::VisitMutWith;
+  use swc_ecma_ast::{EsVersion, Program};
+  use swc_ecma_parser::{Syntax, TsConfig};
+
+  fn visit<T, F: FnOnce(&swc::Compiler, &mut TranspileVisitor, &Program) -> T>(
+    src: &str,
+    f: F,
+  ) -> T {
+    visit_with(src, |_| {}, f)
+  }
+
+  fn visit_with<T, F: FnOnce(&swc::Compiler, &mut TranspileVisitor, &Program) -> T>(
+    src: &str,
+    configure: impl FnOnce(&mut TranspileVisitor),
+    f: F,
+  ) -> T {
+    let cm = Arc::<SourceMap>::default();
+    let c = swc::Compiler::new(cm.clone());
+
+    GLOBALS.set(&Default::default(), || {
+      try_with_handler(cm.clone(), Default::default(), |handler| {
+        let fm = cm.new_source_file(PathBuf::from("test.tsx").into(), src.to_owned());
+        let mut program = c.parse_js(
+          fm,
+          handler,
+          EsVersion::EsNext,
+          Syntax::Typescript(TsConfig {
+            tsx: true,
+            ..Default::default()
+          }),
+          swc::config::IsModule::Bool(true),
+          None,
+        )?;
+
+        let mut v = TranspileVisitor::new();
+        configure(&mut v);
+        program.visit_mut_with(&mut v);
+
+        Ok(f(&c, &mut v, &program))
+      })
+      .unwrap()
+    })
+  }
+
+  fn variable_types(src: &str, names: &[&str]) -> Vec<Option<VarType>> {
+    visit(src, |_, v, _| {
+      names.iter().map(|name| v.get_variable_type(name)).collect()
+    })
+  }
+
+  // Runs the transpiled `src` against the runtime in `test/impl.js`, and returns
+  // every chunk its default export streamed, `None` if node isn't installed
+  fn stream(src: &str) -> Option<Vec<String>> {
+    stream_code(&transpile(src))
+  }
+
+  fn stream_code(code: &str) -> Option<Vec<String>> {
+    let output = crate::utils::node::run(
+      "import Page from './component.mjs';
+       const chunks = [];
+       const controller = { enqueue: (chunk) => chunks.push(chunk) };
+       const [html, later] = await Page();
+       chunks.push(html);
+       await later(controller);
+       console.log(chunks.join('\\n'));",
+      &[("component.mjs", code)],
+    )?;
+    Some(output.lines().map(|l| l.to_owned()).collect())
+  }
+
+  fn transpile(src: &str) -> String {
+    transpile_with(src, |_| {})
+  }
+
+  fn transpile_with(src: &str, configure: impl FnOnce(&mut TranspileVisitor)) -> String {
+    visit_with(src, configure, |c, _, program| {
+      c.print(program, PrintArgs::default()).unwrap().code
+    })
+  }
+
+  #[test]
+  fn return_type_uses_every_return() {
+    assert_eq!(
+      variable_types(
+        "function A({ x }) { if (x) return <a />; return null; }
+         function B({ x }) { if (x) return null; return <b />; }
+         async function C({ x }) { if (x) return null; return <c />; }
+         function D() { return 1; }",
+        &["A", "B", "C", "D"],
+      ),
+      vec![
+        Some(VarType::JSX),
+        Some(VarType::JSX),
+        Some(VarType::AsyncJSX),
+        Some(VarType::Other),
+      ]
+    );
+  }
+
+  #[test]
+  fn nested_functions_do_not_leak_return_type() {
+    assert_eq!(
+      variable_types(
+        "function A() { const f = () => <a />; function g() { return <b />; } return 1; }
+         function B() { const items = [1].map(async (i) => i); return <b />; }
+         const C = () => { const inner = function () { return <c />; }; return null; };",
+        &["A", "B", "C"],
+      ),
+      vec![
+        Some(VarType::Other),
+        Some(VarType::JSX),
+        Some(VarType::Other),
+      ]
+    );
+  }
+
+  #[test]
+  fn function_expressions_and_classes_are_components() {
+    assert_eq!(
+      variable_types(
+        "const A = function A() { return <a />; };
+         const B = async function () { return <b />; };
+         class C { render() { return <c />; } }
+         const D = class { async render() { return <d />; } };
+         class E { format() { return <e />; } }",
+        &["A", "B", "C", "D", "E"],
+      ),
+      vec![
+        Some(VarType::JSX),
+        Some(VarType::AsyncJSX),
+        Some(VarType::JSX),
+        Some(VarType::AsyncJSX),
+        None,
+      ]
+    );
+  }
+
+  #[test]
+  fn class_components_are_constructed_and_rendered() {
+    let code = transpile(
+      "class Card { render() { return <div>{this.props.title}</div>; } }
+       class Feed { async render() { return <ul>{await items()}</ul>; } }
+       export default function Page() { return <main><Card title=\"a\" /><Feed /></main>; }",
+    );
+
+    // Sync ones are rendered in place, async ones behind a boundary
+    let card = code.find("___FRAMEWORK_JS_STRINGIFY___((").unwrap();
+    assert!(code[card..].contains("=>Object.assign(new Card("));
+    let feed = code.find("___FRAMEWORK_JS_ASYNC___(").unwrap();
+    assert!(code[feed..].contains("=>Object.assign(new Feed("));
+  }
+
+  #[test]
+  fn class_components_render_with_their_props() {
+    let Some(chunks) = stream(
+      "class Card { render() { return <h2>{this.props.title}</h2>; } }
+       class Feed { async render() { return <ul><li>{this.props.items[0]}</li></ul>; } }
+       class Named { constructor(props) { this.name = props.name; } render() { return <b>{this.name}</b>; } }
+       export default function Page() {
+         return <main><Card title=\"a\" /><Feed items={['b']} /><Named name=\"c\" /></main>;
+       }",
+    ) else {
+      return;
+    };
+
+    assert!(chunks[0].starts_with("<main><h2>a</h2>"), "{chunks:?}");
+    assert!(chunks[0].contains("<b>c</b>"), "{chunks:?}");
+    assert!(
+      chunks.iter().any(|c| c.contains("\\u003cul>\\u003cli>b")),
+      "{chunks:?}"
+    );
+  }
+
+  #[test]
+  fn async_generators_are_streamed() {
+    let src = "async function* Results() { yield <li>1</li>; yield <li>2</li>; }
+               async function* More() { yield* Results(); }
+               async function* Lines() { yield 'a'; }
+               function* Numbers() { yield <li>1</li>; }
+               export default function Page() { return <ul><Results /></ul>; }";
+
+    assert_eq!(
+      variable_types(src, &["Results", "More", "Lines", "Numbers"]),
+      vec![
+        Some(VarType::StreamJSX),
+        Some(VarType::StreamJSX),
+        Some(VarType::AsyncOther),
+        Some(VarType::Other)
+      ]
+    );
+
+    let code = transpile(src);
+    assert!(code.contains("global.___FRAMEWORK_JS_STREAM___("));
+    assert!(code.contains("=>Results({"));
+    assert!(!code.contains("await Results("));
+  }
+
+  // Neither the component nor the runtime may need `for await` below ES2018
+  #[cfg(feature = "compiler")]
+  #[test]
+  fn lowered_async_generators_still_stream() {
+    let output = crate::transpile(
+      "async function* Results() { yield <li>1</li>; yield <li>2</li>; }
+       export default function Page() { return <ul><Results /></ul>; }",
+      &crate::Options {
+        target: swc_ecma_ast::EsVersion::Es2017,
+        ..crate::Options::default()
+      },
+    )
+    .unwrap();
+    assert!(!include_str!("../test/impl.js").contains("for await ("));
+
+    let Some(chunks) = stream_code(&output.code) else {
+      return;
+    };
+    let inserted = chunks
+      .iter()
+      .filter(|c| c.contains("insertAdjacentHTML"))
+      .collect::<Vec<_>>();
+    assert_eq!(inserted.len(), 2, "{chunks:?}");
+    assert!(inserted[0].contains("\\u003cli>1"));
+    assert!(inserted[1].contains("\\u003cli>2"));
+  }
+
+  #[test]
+  fn nested_async_boundaries_wait_for_their_parent() {
+    let code = transpile(
+      "async function Outer({ children }) { return <section>{children}</section>; }
+       async function Inner() { return <b>inner</b>; }
+       export default function Page() { return <main><Outer><Inner /></Outer></main>; }",
+    );
+
+    // Parents are created first
+    let outer = code.find("___FRAMEWORK_JS_ASYNC___(").unwrap();
+    let inner = code.rfind("___FRAMEWORK_JS_ASYNC___(").unwrap();
+    assert!(code[outer..inner].contains("Outer({"));
+    assert!(code[inner..].contains("Inner({"));
+
+    // Inner gets handed the promise Outer was placed with
+    let placed = code[..outer].rsplit("const ").next().unwrap();
+    let placed = placed.split_whitespace().next().unwrap();
+    assert!(code[inner..].contains(&format!(", {placed});")));
+  }
+
+  #[test]
+  fn nested_async_boundaries_stream_in_order() {
+    let Some(chunks) = stream(
+      "const sleep = (ms) => new Promise((r) => setTimeout(r, ms));
+       async function Outer({ children }) { await sleep(40); return <section>{children}</section>; }
+       async function Inner() { await sleep(1); return <b>inner</b>; }
+       async function Deep() { return <i>deep</i>; }
+       async function Broken() { throw new Error('broken'); }
+       async function Sibling() { return <u>sibling</u>; }
+       function Wrap() { return <em><Deep /></em>; }
+       export default function Page() {
+         return <main><Outer><Inner /><Wrap /></Outer><Broken /><Sibling /></main>;
+       }",
+    ) else {
+      return;
+    };
+
+    let position = |needle: &str| {
+      chunks
+        .iter()
+        .position(|c| c.contains(needle))
+        .unwrap_or_else(|| panic!("{needle} was never streamed: {chunks:#?}"))
+    };
+
+    assert!(chunks[0].starts_with("<main><div id="));
+    // Inner resolves first, but its placeholder is only there once Outer is
+    let outer = position("\\u003csection>");
+    assert!(outer < position("\\u003cb>inner"));
+    // Deep is rendered by a sync component in Outer's props
+    assert!(outer < position("\\u003ci>deep"));
+    // Siblings don't wait on Outer, and a rejected one doesn't take the rest down
+    assert!(position("\\u003cu>sibling") < outer);
+  }
+
+  #[test]
+  fn error_boundaries_stream_their_fallback() {
+    let Some(chunks) = stream(
+      "global.___FRAMEWORK_JS_ON_ERROR___ = (error) => console.log(`reported ${error.message}`);
+       async function Broken() { throw new Error('broken'); }
+       async function Fine() { return <b>fine</b>; }
+       export default function Page() {
+         return <main>
+           <ErrorBoundary fallback={<p>oops</p>}><Broken /><Fine /></ErrorBoundary>
+           <ErrorBoundary fallback=<p>whoops</p>><Broken /></ErrorBoundary>
+           <Broken />
+         </main>;
+       }",
+    ) else {
+      return;
+    };
+
+    assert_eq!(chunks.iter().filter(|c| *c == "reported broken").count(), 3);
+    // The one in the boundary gets the fallback, the other one is just removed
+    assert_eq!(
+      chunks
+        .iter()
+        .filter(|c| c.contains("\\u003cp>oops"))
+        .count(),
+      1
+    );
+    assert_eq!(
+      chunks
+        .iter()
+        .filter(|c| c.contains("outerHTML=\"\""))
+        .count(),
+      1
+    );
+    assert!(chunks.iter().any(|c| c.contains("\\u003cb>fine")));
+    assert!(chunks.iter().any(|c| c.contains("\\u003cp>whoops")));
+  }
+
+  #[test]
+  fn imports_shadow_the_built_ins() {
+    let code = transpile(
+      "import ErrorBoundary from './boundary';
+       import { css } from './styles';
+       export default function Page() {
+         return <ErrorBoundary fallback={<p>oops</p>}><main class={css`color: red`} /></ErrorBoundary>;
+       }",
+    );
+    // Rendered like any other component, with `fallback` as one of its props
+    assert!(code.contains("=>ErrorBoundary({"), "{code}");
+    assert!(code.contains("fallback: "));
+    assert!(code.contains("(css`color: red`)"));
+  }
+
+  #[test]
+  fn static_components_are_inlined() {
+    let code = transpile(
+      "function Navigation() { return <nav><a href=\"#\">Home</a></nav>; }
+       const Logo = () => <img src=\"/logo.png\" />;
+       function Header() { return <header><Logo /><Navigation /></header>; }
+       function Hello({ name }) { return <div>Hello, {name}</div>; }
+       export default function Page() { return <main><Header /><Hello name=\"Marko\" /><Navigation>!</Navigation></main>; }",
+    );
+
+    assert!(code.contains(
+      "`<main><header><img src=\"/logo.png\"></img><nav><a href=\"#\">Home</a></nav></header>`"
+    ));
+    assert!(!code.contains("Header({"));
+    // Components with props or children are still called
+    assert!(code.contains("Hello({"));
+    assert!(code.contains("Navigation({"));
+  }
+
+  #[test]
+  fn long_static_html_is_hoisted() {
+    let html =
+      "<main><section><h1>Welcome</h1><p>This page is entirely static.</p></section></main>";
+    let src = format!(
+      "export function A() {{ return {html}; }}
+       export function B({{ name }}) {{ return <div>{{name}}{html}</div>; }}
+       export function C() {{ return <b>Short</b>; }}
+       export function D() {{ return {html}; }}
+       export default function Page() {{ return <B name=\"x\" />; }}"
+    );
+    let code = transpile(&src);
+
+    // `A` and `D` share one constant, the long quasi in `B` gets its own,
+    // all of them declared at the top of the module
+    assert_eq!(code.matches(html).count(), 2);
+    assert!(code.trim_start().starts_with("const "));
+    assert!(code.contains("`<b>Short</b>`"));
+
+    if let Some(output) = stream(&src) {
+      assert_eq!(output, vec![format!("<div>x{html}</div>")]);
+    }
+  }
+
+  #[test]
+  fn styles_accept_any_expression() {
+    let src = "const theme = { card: { color: 'red', fontSize: 12 } };
+       const getStyle = () => ({ marginTop: 4 });
+       export default function Page() {
+         const dark = true;
+         return <main><p style={getStyle()}>a</p><p style={theme.card}>b</p><p
+           style={dark ? { color: 'white' } : theme.card}>c</p><p
+           style={{ opacity: dark ? 0.5 : 1, width: dark ? 10 : 'auto' }}>d</p></main>;
+       }";
+
+    let code = transpile(src);
+    assert!(code.contains("${dark ? \"opacity: 0.5\" : \"opacity: 1\"}"));
+
+    if let Some(output) = stream(src) {
+      assert_eq!(
+        output,
+        vec![concat!(
+          "<main><p style=\"margin-top: 4px\">a</p>",
+          "<p style=\"color: red;font-size: 12px\">b</p>",
+          "<p style=\"color: white\">c</p>",
+          "<p style=\"opacity: 0.5;width: 10px\">d</p></main>"
+        )]
+      );
+    }
+  }
+
+  #[test]
+  fn empty_styles_are_skipped() {
+    let src = "const theme = { color: null, margin: 0, padding: undefined, border: false };
+       export default function Page() {
+         const color = undefined;
+         return <main><p style={{ color, fontSize: 12 }}>a</p><p style={theme}>b</p><p
+           style={null}>c</p><p style=\"color: red; content: '&'\">d</p><p
+           style={'margin: 0'}>e</p></main>;
+       }";
+
+    if let Some(output) = stream(src) {
+      assert_eq!(
+        output,
+        vec![concat!(
+          "<main><p style=\"font-size: 12px\">a</p>",
+          "<p style=\"margin: 0\">b</p>",
+          "<p>c</p>",
+          "<p style=\"color: red; content: &#x27;&amp;&#x27;\">d</p>",
+          "<p style=\"margin: 0\">e</p></main>"
+        )]
+      );
+    }
+  }
+
+  #[test]
+  fn css_templates_are_extracted() {
+    let src = "const button = css`color: red; &:hover { color: blue; }`;
+       const same = css`color: red; &:hover { color: blue; }`;
+       const title = css`font-weight: bold;`;
+       export default function Page() {
+         return <html><head><title>Page</title></head><body><h1 class={title}>Hi</h1><button
+           class={button}>Go</button></body></html>;
+       }";
+
+    let button = crate::utils::css::class_name("color: red; &:hover { color: blue; }");
+    let title = crate::utils::css::class_name("font-weight: bold;");
+
+    // Identical templates share a rule
+    let code = transpile(src);
+    assert_eq!(code.matches("color: blue").count(), 1);
+
+    if let Some(output) = stream(src) {
+      assert_eq!(
+        output,
+        vec![format!(
+          "<html><head><title>Page</title><style>.{button}{{color: red; &:hover {{ color: blue; }}}}.{title}{{font-weight: bold;}}</style></head><body><h1 class=\"{title}\">Hi</h1><button class=\"{button}\">Go</button></body></html>"
+        )]
+      );
+    }
+  }
+
+  #[test]
+  fn pages_only_get_the_rules_they_use() {
+    let src = "const title = css`font-weight: bold;`;
+       const other = css`color: red;`;
+       const late = css`color: blue;`;
+       export function Other() {
+         return <p class={other}>Other</p>;
+       }
+       async function Late() {
+         return <p class={late}>Late</p>;
+       }
+       export default function Page() {
+         return <html><head></head><body><h1 class={title}>Hi</h1><Late /><p
+           class={title}>Again</p></body></html>;
+       }";
+
+    let title = crate::utils::css::class_name("font-weight: bold;");
+    let late = crate::utils::css::class_name("color: blue;");
+
+    if let Some(output) = stream(src) {
+      let html = output.join("\n");
+      assert!(html.starts_with(&format!(
+        "<html><head><style>.{title}{{font-weight: bold;}}</style></head>"
+      )));
+      // Streamed html brings the rules the `<head>` didn't have
+      assert_eq!(html.matches(&format!(".{title}{{")).count(), 1);
+      assert_eq!(html.matches(&format!(".{late}{{color: blue;}}")).count(), 1);
+      assert!(!html.contains("color: red"), "{html}");
+    }
+
+    let src = "const unused = css`color: red;`;
+       export default function Page() {
+         return <html><head><title>Page</title></head><body>Hi</body></html>;
+       }";
+    if let Some(output) = stream(src) {
+      assert_eq!(
+        output,
+        vec!["<html><head><title>Page</title></head><body>Hi</body></html>".to_owned()]
+      );
+    }
+  }
+
+  #[test]
+  fn static_styles_become_atomic_classes() {
+    let src = "export default function Page() {
+         const color = 'red';
+         return <html><head></head><body style={{ backgroundColor: '#121212', color: 'white' }}><p
+           className=\"text\" style={{ color: 'white', margin: null }}>a</p><p
+           style={{ color }}>b</p></body></html>;
+       }";
+
+    let code = transpile_with(src, |v| v.options.atomic_styles = true);
+    // Dynamic styles are left as they are
+    assert_eq!(code.matches("style=").count(), 1);
+
+    let background = crate::utils::css::class_name("background-color: #121212");
+    let white = crate::utils::css::class_name("color: white");
+
+    if let Some(output) = stream_code(&code) {
+      assert_eq!(
+        output,
+        vec![format!(
+          "<html><head><style>.{white}{{color: white}}.{background}{{background-color: #121212}}</style></head><body class=\"{background} {white}\"><p class=\"text {white}\">a</p><p style=\"color: red\">b</p></body></html>"
+        )]
+      );
+    }
+  }
+
+  #[test]
+  fn atomic_shorthands_keep_their_declaration_order() {
+    let src = "export default function Page() {
+         return <main><p style={{ margin: 0, color: 'red' }}>a</p><p
+           style={{ marginTop: 4, margin: 0 }}>b</p><p
+           style={{ inset: 0, top: 4 }}>c</p></main>;
+       }";
+
+    let code = transpile_with(src, |v| v.options.atomic_styles = true);
+    let margin = crate::utils::css::class_name("margin: 0");
+    let red = crate::utils::css::class_name("color: red");
+    let margins = crate::utils::css::class_name("margin-top: 4px;margin: 0");
+    let inset = crate::utils::css::class_name("inset: 0;top: 4px");
+
+    // `.margin` is before `.margin-top` in the stylesheet, so on their own
+    // `b` would end up with a top margin
+    assert!(code.contains(&format!("class=\"{margin} {red}\"")));
+    assert!(code.contains(&format!("class=\"{margins}\"")));
+    assert!(code.contains(&format!("class=\"{inset}\"")));
+  }
+
+  #[test]
+  fn atomic_classes_share_a_bare_class_attribute() {
+    let src = "export default function Page() {
+         return <main><p class style={{ color: 'red' }}>a</p><p style={{ color: 'red' }}
+           className>b</p><p class>c</p></main>;
+       }";
+
+    let code = transpile_with(src, |v| v.options.atomic_styles = true);
+    let red = crate::utils::css::class_name("color: red");
+
+    assert_eq!(code.matches(&format!("<p class=\"{red}\">")).count(), 2);
+    assert!(code.contains("<p class=\"true\">c</p>"));
+    assert_eq!(code.matches("class=").count(), 3);
+  }
+
+  #[test]
+  fn class_names_accept_arrays_and_objects() {
+    let src = "export default function Page() {
+         const active = true;
+         const extra = ['x', null, { y: 0, z: 1 }];
+         return <main className={['btn', false, 'big']}><p class={{ btn: true, hidden: false }}>a</p><p
+           className={['btn', active && 'active', !active && 'inactive']}>b</p><p
+           className={{ btn: 1, active }}>c</p><p className={active ? 'on' : null}>d</p><p
+           className={extra}>e</p><p className={['a&b', 0, undefined]}>f</p></main>;
+       }";
+
+    let code = transpile(src);
+    assert!(code.contains("<main class=\"btn big\"><p class=\"btn\">"));
+    assert!(code.contains("class=\"a&amp;b\""));
+
+    if let Some(output) = stream(src) {
+      assert_eq!(
+        output,
+        vec![concat!(
+          "<main class=\"btn big\"><p class=\"btn\">a</p>",
+          "<p class=\"btn active\">b</p>",
+          "<p class=\"btn active\">c</p>",
+          "<p class=\"on\">d</p>",
+          "<p class=\"x z\">e</p>",
+          "<p class=\"a&amp;b\">f</p></main>"
+        )]
+      );
+    }
+  }
+
+  #[test]
+  fn components_are_recorded() {
+    let (components, renders) = visit(
+      "import { Card } from './card';
+       const Title = () => <h1>Title</h1>;
+       async function Feed() { return <ul>{await items()}</ul>; }
+       export const Page = () => <main><Title /><Feed /><Card /></main>;
+       export default Page;
+       const notAComponent = () => 1;",
+      |_, v, _| v.take_components(),
+    );
+
+    let summary = components
+      .iter()
+      .map(|c| (c.name.as_str(), c.export.as_deref(), c.var_type))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      summary,
+      vec![
+        ("Title", None, VarType::JSX),
+        ("Feed", None, VarType::AsyncJSX),
+        ("Page", Some("Page"), VarType::JSX),
+      ]
+    );
+
+    let page = &components[2];
+    assert_eq!(page.async_children, vec!["Feed", "Card"]);
+    assert!(page.runtime_helpers.contains("___FRAMEWORK_JS_ASYNC___"));
+    assert!(renders.iter().all(|r| r.from.as_deref() == Some("Page")));
+  }
+
+  #[test]
+  fn options_configure_the_output() {
+    let src = "async function* Results() { yield <li>1</li>; yield <li>2</li>; }
+               export default function Page() { return <form><label htmlFor=\"q\" ariaLabel=\"Query\">Q</label><ul><Results /></ul></form>; }";
+
+    let configure = |v: &mut TranspileVisitor| {
+      v.options
+        .attributes
+        .insert("htmlFor".to_owned(), "for".to_owned());
+      v.options
+        .attributes
+        .insert("ariaLabel".to_owned(), "aria-label".to_owned());
+      v.options.runtime.global = "globalThis".to_owned();
+      v.options.streaming = Streaming::Buffered;
+    };
+    let code = transpile_with(src, |v| {
+      configure(v);
+      v.options.runtime.import_source = Some("framework-js/runtime".to_owned());
+    });
+    assert!(
+      code.starts_with("import \"framework-js/runtime\";"),
+      "{code}"
+    );
+    assert!(
+      code.contains("<label for=\"q\" aria-label=\"Query\">"),
+      "{code}"
+    );
+    assert!(code.contains("globalThis.___FRAMEWORK_JS_COLLECT___("));
+    assert!(!code.contains("global.___FRAMEWORK_JS_"));
+
+    if let Some(output) = stream_code(&transpile_with(src, configure)) {
+      // Both chunks replace the placeholder in one script
+      assert_eq!(output.len(), 2);
+      assert!(output[1].contains(r#"outerHTML="\u003cli>1\u003c/li>\u003cli>2\u003c/li>""#));
     }
   }
 }
@@ -0,0 +1,112 @@
+use crate::tpl_wrapper::TplWrapper;
+use swc_common::util::take::Take;
+use swc_ecma_ast::{
+  ArrayPat, ArrowExpr, BinExpr, BinaryOp, CallExpr, Callee, CondExpr, Expr, Ident, Lit, MemberExpr,
+  MemberProp, ParenExpr, Pat, Regex, UnaryExpr, UnaryOp,
+};
+
+// `{...attributes}` on an element, rendered when the element is:
+// Object.entries(attributes).map(([key, value]) => `${key}="${value ? (
+//   typeof value === "string" ? value : value instanceof RegExp ? value.toString() : JSON.stringify(value)
+// ).replace(/"/mg, "\\\"") : "true"}"`).join(" ")
+pub fn spread_attributes(attributes: Expr) -> Expr {
+  let key = || Box::new(Expr::Ident("key".into()));
+  let value = || Box::new(Expr::Ident("value".into()));
+
+  let string = Expr::Cond(CondExpr {
+    test: Box::new(Expr::Bin(BinExpr {
+      op: BinaryOp::EqEqEq,
+      left: Box::new(Expr::Unary(UnaryExpr {
+        op: UnaryOp::TypeOf,
+        arg: value(),
+        ..UnaryExpr::dummy()
+      })),
+      right: Box::new(Expr::Lit(Lit::Str("string".into()))),
+      ..BinExpr::dummy()
+    })),
+    cons: value(),
+    alt: Box::new(Expr::Cond(CondExpr {
+      test: Box::new(Expr::Bin(BinExpr {
+        op: BinaryOp::InstanceOf,
+        left: value(),
+        right: Box::new(Expr::Ident("RegExp".into())),
+        ..BinExpr::dummy()
+      })),
+      cons: Box::new(call(method(value(), "toString"), vec![])),
+      alt: Box::new(call(
+        method(Box::new(Expr::Ident("JSON".into())), "stringify"),
+        vec![*value()],
+      )),
+      ..CondExpr::dummy()
+    })),
+    ..CondExpr::dummy()
+  });
+  let escaped = call(
+    method(
+      Box::new(Expr::Paren(ParenExpr {
+        expr: Box::new(string),
+        ..ParenExpr::dummy()
+      })),
+      "replace",
+    ),
+    vec![
+      Expr::Lit(Lit::Regex(Regex {
+        exp: "\"".into(),
+        flags: "mg".into(),
+        ..Regex::dummy()
+      })),
+      Expr::Lit(Lit::Str("\\\"".into())),
+    ],
+  );
+
+  let mut attribute = TplWrapper::new();
+  attribute.append_expr(*key());
+  attribute.append_quasi("=\"");
+  attribute.append_expr(Expr::Cond(CondExpr {
+    test: value(),
+    cons: Box::new(escaped),
+    alt: Box::new(Expr::Lit(Lit::Str("true".into()))),
+    ..CondExpr::dummy()
+  }));
+  attribute.append_quasi("\"");
+
+  let to_attribute = Expr::Arrow(ArrowExpr {
+    params: vec![Pat::Array(ArrayPat {
+      elems: vec![
+        Some(Pat::Ident(Ident::from("key").into())),
+        Some(Pat::Ident(Ident::from("value").into())),
+      ],
+      span: Default::default(),
+      optional: false,
+      type_ann: None,
+    })],
+    body: Box::new(Expr::Tpl(attribute.build()).into()),
+    ..ArrowExpr::dummy()
+  });
+
+  let entries = call(
+    method(Box::new(Expr::Ident("Object".into())), "entries"),
+    vec![attributes],
+  );
+  let attributes = call(method(Box::new(entries), "map"), vec![to_attribute]);
+  call(
+    method(Box::new(attributes), "join"),
+    vec![Expr::Lit(Lit::Str(" ".into()))],
+  )
+}
+
+fn method(obj: Box<Expr>, name: &str) -> Expr {
+  Expr::Member(MemberExpr {
+    obj,
+    prop: MemberProp::Ident(name.into()),
+    ..MemberExpr::dummy()
+  })
+}
+
+fn call(callee: Expr, args: Vec<Expr>) -> Expr {
+  Expr::Call(CallExpr {
+    callee: Callee::Expr(Box::new(callee)),
+    args: args.into_iter().map(|arg| Box::new(arg).into()).collect(),
+    ..CallExpr::dummy()
+  })
+}
@@ -0,0 +1,129 @@
+use super::{call_framework_fn, constant::Constant, style::escape_html};
+use swc_common::util::take::Take;
+use swc_ecma_ast::{
+  ArrayLit, BinExpr, BinaryOp, CondExpr, Expr, ExprOrSpread, KeyValueProp, Lit, ObjectLit,
+  ParenExpr, Prop, PropName, PropOrSpread,
+};
+
+// A `class`/`className` value, in the same shapes `clsx` takes: strings,
+// arrays and objects, with the falsy entries left out. Folded into a
+// string literal when it's known at compile time, otherwise processed
+// with `___FRAMEWORK_JS_CLASS___`
+pub fn class_to_string(class: Expr) -> Expr {
+  match fold_class(class) {
+    Ok(class) => Expr::Lit(Lit::Str(escape_html(class).into())),
+    Err(class) => call_framework_fn("___FRAMEWORK_JS_CLASS___", vec![Box::new(class).into()]),
+  }
+}
+
+// `Ok` with the unescaped class names, or `Err` with what's left for the
+// runtime, with as much of it folded as we could
+fn fold_class(class: Expr) -> Result<String, Expr> {
+  if let Some(constant) = Constant::evaluate(&class) {
+    return Ok(match constant {
+      Constant::Str(_) | Constant::Num(_) if constant.is_truthy() => constant.to_js_string(),
+      // Just like `clsx`, `true` doesn't stand for a class name
+      _ => String::new(),
+    });
+  }
+
+  match class {
+    Expr::Paren(ParenExpr { expr, .. }) => fold_class(*expr),
+    Expr::Cond(CondExpr {
+      test, cons, alt, ..
+    }) => Err(Expr::Cond(CondExpr {
+      test,
+      cons: Box::new(folded_to_expr(fold_class(*cons))),
+      alt: Box::new(folded_to_expr(fold_class(*alt))),
+      ..CondExpr::dummy()
+    })),
+    Expr::Array(array) => {
+      fold_entries(
+        array
+          .elems
+          .into_iter()
+          .flatten()
+          .map(|elem| match elem.spread {
+            Some(_) => Err(Expr::Array(ArrayLit {
+              elems: vec![Some(elem)],
+              ..ArrayLit::dummy()
+            })),
+            None => fold_class(*elem.expr),
+          }),
+      )
+    }
+    Expr::Object(obj) => match object_entries(&obj) {
+      Some(entries) => fold_entries(entries.into_iter()),
+      None => Err(Expr::Object(obj)),
+    },
+    class => Err(class),
+  }
+}
+
+// `{ btn: true, active }` as `["btn", active && "active"]`, `None` if
+// the object has keys we can't see at compile time
+fn object_entries(obj: &ObjectLit) -> Option<Vec<Result<String, Expr>>> {
+  let mut entries = vec![];
+
+  for prop in obj.props.iter() {
+    let PropOrSpread::Prop(prop) = prop else {
+      return None;
+    };
+
+    let (key, value) = match &**prop {
+      Prop::Shorthand(name) => (name.sym.to_string(), Expr::Ident(name.clone())),
+      Prop::KeyValue(KeyValueProp { key, value }) => {
+        let key = match key {
+          PropName::Ident(i) => i.sym.to_string(),
+          PropName::Str(str) => str.value.to_string(),
+          _ => return None,
+        };
+        (key, *value.clone())
+      }
+      _ => return None,
+    };
+
+    entries.push(match Constant::evaluate(&value) {
+      Some(value) if value.is_truthy() => Ok(key),
+      Some(_) => Ok(String::new()),
+      None => Err(Expr::Bin(BinExpr {
+        op: BinaryOp::LogicalAnd,
+        left: Box::new(value),
+        right: Box::new(Expr::Lit(Lit::Str(key.into()))),
+        ..BinExpr::dummy()
+      })),
+    });
+  }
+
+  Some(entries)
+}
+
+fn fold_entries<I: Iterator<Item = Result<String, Expr>>>(entries: I) -> Result<String, Expr> {
+  let entries = entries
+    .filter(|entry| !matches!(entry, Ok(class) if class.is_empty()))
+    .collect::<Vec<_>>();
+
+  if entries.iter().all(|entry| entry.is_ok()) {
+    return Ok(entries.into_iter().flatten().collect::<Vec<_>>().join(" "));
+  }
+
+  Err(Expr::Array(ArrayLit {
+    elems: entries
+      .into_iter()
+      .map(|entry| {
+        Some(ExprOrSpread {
+          spread: None,
+          expr: Box::new(folded_to_expr(entry)),
+        })
+      })
+      .collect(),
+    ..ArrayLit::dummy()
+  }))
+}
+
+fn folded_to_expr(folded: Result<String, Expr>) -> Expr {
+  match folded {
+    Ok(class) => Expr::Lit(Lit::Str(class.into())),
+    Err(class) => class,
+  }
+}
@@ -0,0 +1,150 @@
+use num_traits::ToPrimitive;
+use swc_ecma_ast::{BinExpr, BinaryOp, Expr, Lit, ParenExpr, UnaryExpr, UnaryOp};
+
+// A value known at compile time, with the same semantics it'd have in JS
+#[derive(Debug, PartialEq)]
+pub enum Constant {
+  Str(String),
+  Num(f64),
+  Bool(bool),
+  Null,
+  Undefined,
+}
+
+impl Constant {
+  pub fn evaluate(expr: &Expr) -> Option<Constant> {
+    match expr {
+      Expr::Lit(Lit::Str(str)) => Some(Constant::Str(str.value.to_string())),
+      Expr::Lit(Lit::Num(num)) => Some(Constant::Num(num.value)),
+      Expr::Lit(Lit::BigInt(num)) => num.value.to_f64().map(Constant::Num),
+      Expr::Lit(Lit::Bool(b)) => Some(Constant::Bool(b.value)),
+      Expr::Lit(Lit::Null(_)) => Some(Constant::Null),
+      Expr::Ident(ident) if ident.sym == "undefined" => Some(Constant::Undefined),
+      Expr::Paren(ParenExpr { expr, .. }) => Constant::evaluate(expr),
+      Expr::Tpl(tpl) => {
+        let mut string = String::new();
+        for (i, quasi) in tpl.quasis.iter().enumerate() {
+          string.push_str(quasi.cooked.as_ref().unwrap_or(&quasi.raw));
+          if let Some(expr) = tpl.exprs.get(i) {
+            string.push_str(&Constant::evaluate(expr)?.to_js_string());
+          }
+        }
+        Some(Constant::Str(string))
+      }
+      Expr::Unary(UnaryExpr { op, arg, .. }) => match (op, Constant::evaluate(arg)?) {
+        (UnaryOp::Minus, Constant::Num(num)) => Some(Constant::Num(-num)),
+        (UnaryOp::Plus, Constant::Num(num)) => Some(Constant::Num(num)),
+        (UnaryOp::Void, _) => Some(Constant::Undefined),
+        _ => None,
+      },
+      Expr::Bin(BinExpr {
+        op, left, right, ..
+      }) => {
+        let left = Constant::evaluate(left)?;
+        let right = Constant::evaluate(right)?;
+        match (op, left, right) {
+          (BinaryOp::Add, Constant::Num(l), Constant::Num(r)) => Some(Constant::Num(l + r)),
+          (BinaryOp::Add, l @ Constant::Str(_), r) | (BinaryOp::Add, l, r @ Constant::Str(_)) => {
+            Some(Constant::Str(format!(
+              "{}{}",
+              l.to_js_string(),
+              r.to_js_string()
+            )))
+          }
+          (BinaryOp::Sub, Constant::Num(l), Constant::Num(r)) => Some(Constant::Num(l - r)),
+          (BinaryOp::Mul, Constant::Num(l), Constant::Num(r)) => Some(Constant::Num(l * r)),
+          (BinaryOp::Div, Constant::Num(l), Constant::Num(r)) => Some(Constant::Num(l / r)),
+          _ => None,
+        }
+        // Rust and JS don't agree on how to print these
+        .filter(|c| !matches!(c, Constant::Num(num) if !num.is_finite()))
+      }
+      _ => None,
+    }
+  }
+
+  pub fn to_js_string(&self) -> String {
+    match self {
+      Constant::Str(str) => str.clone(),
+      Constant::Num(num) => number_to_js_string(*num),
+      Constant::Bool(b) => b.to_string(),
+      Constant::Null => "null".to_owned(),
+      Constant::Undefined => "undefined".to_owned(),
+    }
+  }
+
+  pub fn is_truthy(&self) -> bool {
+    match self {
+      Constant::Str(str) => !str.is_empty(),
+      Constant::Num(num) => *num != 0.0 && !num.is_nan(),
+      Constant::Bool(b) => *b,
+      Constant::Null | Constant::Undefined => false,
+    }
+  }
+}
+
+// `String(num)`: the shortest digits that round-trip, like Rust's, but with
+// an exponent past 21 digits or 6 leading zeros
+// https://tc39.es/ecma262/#sec-numeric-types-number-tostring
+pub fn number_to_js_string(num: f64) -> String {
+  if num.is_nan() {
+    return "NaN".to_owned();
+  }
+  // JS prints `-0` as `0`
+  if num == 0.0 {
+    return "0".to_owned();
+  }
+  if num < 0.0 {
+    return format!("-{}", number_to_js_string(-num));
+  }
+  if num.is_infinite() {
+    return "Infinity".to_owned();
+  }
+
+  // `d.ddde±x`, the digits without the point, and where the point goes
+  let exp = format!("{num:e}");
+  let (mantissa, exponent) = exp.split_once('e').unwrap();
+  let digits = mantissa.replace('.', "");
+  let k = digits.len() as i32;
+  let n = exponent.parse::<i32>().unwrap() + 1;
+
+  if k <= n && n <= 21 {
+    format!("{digits}{}", "0".repeat((n - k) as usize))
+  } else if 0 < n && n <= 21 {
+    format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+  } else if -6 < n && n <= 0 {
+    format!("0.{}{digits}", "0".repeat(-n as usize))
+  } else {
+    let sign = if n - 1 < 0 { '-' } else { '+' };
+    match k {
+      1 => format!("{digits}e{sign}{}", (n - 1).abs()),
+      _ => format!("{}.{}e{sign}{}", &digits[..1], &digits[1..], (n - 1).abs()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::number_to_js_string;
+
+  #[test]
+  fn numbers_print_like_js() {
+    let cases = [
+      (0.0, "0"),
+      (-0.0, "0"),
+      (1.5, "1.5"),
+      (-42.0, "-42"),
+      (123456789012345680000.0, "123456789012345680000"),
+      (1e21, "1e+21"),
+      (1.5e300, "1.5e+300"),
+      (0.000001, "0.000001"),
+      (1e-7, "1e-7"),
+      (-1.25e-7, "-1.25e-7"),
+      (5e-324, "5e-324"),
+      (f64::INFINITY, "Infinity"),
+    ];
+    for (num, js) in cases {
+      assert_eq!(number_to_js_string(num), js);
+    }
+  }
+}
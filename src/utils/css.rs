@@ -0,0 +1,115 @@
+use crate::{tpl_wrapper::TplWrapper, utils};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use swc_common::Span;
+use swc_ecma_ast::{Expr, ExprStmt, KeyValueProp, ObjectLit, Prop, PropName, PropOrSpread, Stmt};
+
+// Where a `<head>` gets the `<style>` with the rules its page uses
+pub const STYLESHEET_MARKER: &str = "<!--framework-js-stylesheet-->";
+
+// Rules extracted out of the components. Every module registers its own with
+// `___FRAMEWORK_JS_CSS___`, and a page only gets the ones its html uses
+#[derive(Default)]
+pub struct Stylesheet {
+  // Class name and its rule, in stylesheet order
+  rules: Vec<(String, String)>,
+  // Class name => its declarations
+  class_names: HashMap<String, String>,
+}
+
+impl Stylesheet {
+  // Identical declarations share one class name, and one rule
+  pub fn add_rule<S: AsRef<str>>(&mut self, declarations: S) -> String {
+    let declarations = declarations.as_ref().trim();
+
+    // Two different rules never end up with the same class, even if they hash the same
+    let mut class_name = class_name(declarations);
+    let mut attempt = 0;
+    loop {
+      match self.class_names.get(&class_name) {
+        Some(existing) if existing == declarations => return class_name,
+        Some(_) => {
+          attempt += 1;
+          class_name = self::class_name(&format!("{declarations}\0{attempt}"));
+        }
+        None => break,
+      }
+    }
+
+    self
+      .class_names
+      .insert(class_name.clone(), declarations.to_owned());
+    self.rules.push((
+      class_name.clone(),
+      format!(".{class_name}{{{declarations}}}"),
+    ));
+    class_name
+  }
+
+  // `global.___FRAMEWORK_JS_CSS___({ _class: `...` });`, `None` if there are no rules
+  pub fn take_registration(&mut self) -> Option<Stmt> {
+    if self.rules.is_empty() {
+      return None;
+    }
+
+    let rules = self
+      .rules
+      .drain(..)
+      .map(|(class_name, rule)| {
+        PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+          key: PropName::Str(class_name.into()),
+          value: Box::new(Expr::Tpl(TplWrapper::from_quasi(rule).build())),
+        })))
+      })
+      .collect();
+    self.class_names.clear();
+
+    Some(Stmt::Expr(ExprStmt {
+      span: Span::default(),
+      expr: Box::new(utils::call_framework_fn(
+        "___FRAMEWORK_JS_CSS___",
+        vec![Box::new(Expr::Object(ObjectLit {
+          props: rules,
+          span: Span::default(),
+        }))
+        .into()],
+      )),
+    }))
+  }
+}
+
+// Stable between builds and compiler versions, so the class names don't
+// change unless the css does. The first 64 bits of its SHA-256
+pub fn class_name(declarations: &str) -> String {
+  let hash = Sha256::digest(declarations.as_bytes());
+  let hash = u64::from_be_bytes(hash[..8].try_into().unwrap());
+  format!("_{hash:016x}")
+}
+
+#[cfg(test)]
+mod test {
+  use super::{class_name, Stylesheet};
+
+  #[test]
+  fn class_names_are_stable() {
+    assert_eq!(class_name("color: red"), "_35eac301459fc03d");
+  }
+
+  #[test]
+  fn colliding_class_names_get_another_one() {
+    let mut stylesheet = Stylesheet::default();
+    // As if `color: blue` hashed the same as `color: red`
+    let red = class_name("color: red");
+    stylesheet
+      .class_names
+      .insert(red.clone(), "color: blue".to_owned());
+
+    let class = stylesheet.add_rule("color: red");
+    assert_ne!(class, red);
+    assert_eq!(stylesheet.add_rule("color: red"), class);
+    assert_eq!(
+      stylesheet.rules,
+      vec![(class.clone(), format!(".{class}{{color: red}}"))]
+    );
+  }
+}
@@ -1,34 +1,47 @@
-use crate::transpiler::{ComponentType, ToCreateAsync, TransfromedJSX, TranspileVisitor, VarType};
+use crate::manifest::Boundary;
+use crate::tpl_wrapper::TplWrapper;
+use crate::transpiler::{
+  self, AsyncBoundary, ComponentType, CustomComponent, Later, ToCreateAsync, TransfromedJSX,
+  TranspileVisitor, VarType,
+};
 use rand::{distributions::Alphanumeric, Rng};
 use stringify::Stringify;
-use swc::PrintArgs;
-use swc_common::{util::take::Take, Span};
+use swc_common::{errors::HANDLER, util::take::Take, Span};
+use swc_core::ecma::visit::{VisitMut, VisitMutWith};
 use swc_ecma_ast::{
-  AwaitExpr, CallExpr, Callee, Expr, ExprOrSpread, Ident, MemberExpr, MemberProp,
+  CallExpr, Callee, Expr, ExprOrSpread, Ident, JSXElement, MemberExpr, MemberProp,
 };
 
-pub mod path;
+mod attributes;
+mod class;
+pub mod constant;
+pub mod css;
+#[cfg(test)]
+pub mod node;
 pub mod stringify;
 mod style;
-pub use style::style_object_to_string;
-
-pub fn expr_to_string(compiler: &swc::Compiler, expr: &Expr) -> String {
-  return compiler.print(expr, PrintArgs::default()).unwrap().code;
-}
+pub use attributes::spread_attributes;
+pub use class::class_to_string;
+pub use style::{has_overlapping_declarations, static_style_declarations, style_to_string};
 
 pub fn generate_random_variable_name(len: usize) -> String {
-  return format!(
+  format!(
     "_{}",
     rand::thread_rng()
       .sample_iter(&Alphanumeric)
       .take(len)
       .map(char::from)
       .collect::<String>(),
-  );
+  )
+}
+
+// Through swc's handler, so the compile fails with every error in the file
+pub fn report_error<S: AsRef<str>>(span: Span, message: S) {
+  HANDLER.with(|handler| handler.struct_span_err(span, message.as_ref()).emit());
 }
 
 pub fn call_framework_fn<S: AsRef<str>>(fn_name: S, args: Vec<ExprOrSpread>) -> Expr {
-  return Expr::Call(CallExpr {
+  Expr::Call(CallExpr {
     callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
       obj: Box::new(Expr::Ident("global".into())),
       prop: MemberProp::Ident(fn_name.as_ref().into()),
@@ -36,44 +49,84 @@ pub fn call_framework_fn<S: AsRef<str>>(fn_name: S, args: Vec<ExprOrSpread>) ->
     }))),
     args,
     ..CallExpr::dummy()
-  });
+  })
+}
+
+// The helpers are emitted on `global`, this moves them to where the runtime
+// config says they are
+pub fn rename_runtime_global<N: VisitMutWith<RuntimeGlobal>>(node: &mut N, global: &str) {
+  if global == "global" {
+    return;
+  }
+  node.visit_mut_with(&mut RuntimeGlobal(global.into()));
+}
+
+pub struct RuntimeGlobal(Ident);
+
+impl VisitMut for RuntimeGlobal {
+  fn visit_mut_member_expr(&mut self, member: &mut MemberExpr) {
+    member.visit_mut_children_with(self);
+
+    let is_helper =
+      matches!(&member.prop, MemberProp::Ident(prop) if prop.sym.starts_with("___FRAMEWORK_JS_"));
+    match &mut *member.obj {
+      Expr::Ident(obj) if is_helper && obj.sym == "global" => *obj = self.0.clone(),
+      _ => {}
+    }
+  }
 }
 
 pub fn call_framework_stringify(expr: Box<Expr>, later_create_ident: Ident) -> Expr {
-  return call_framework_fn(
+  call_framework_fn(
     "___FRAMEWORK_JS_STRINGIFY___",
     vec![
       expr.into(),
       Box::new(Expr::Ident(later_create_ident)).into(),
     ],
-  );
+  )
 }
 
 pub enum Processed {
-  Async(String),
+  // The `<div id>` placeholder
+  Async(Expr),
   Sync(Expr),
 }
 
+// `${prefix}id`, the id an async boundary gets at runtime
+pub fn boundary_id(v: &TranspileVisitor, id: &str) -> Expr {
+  let mut tpl = TplWrapper::new();
+  tpl.append_expr(Expr::Ident(v.boundary_id_prefix_ident.clone()));
+  tpl.append_quasi(id);
+  Expr::Tpl(tpl.build())
+}
+
 pub fn process_transformed_jsx(
   (transformed, custom): TransfromedJSX,
   v: &TranspileVisitor,
   to_create: &mut ToCreateAsync,
 ) -> Processed {
   if let ComponentType::Custom(name) = custom {
-    let is_async = v.get_variable_type(&name.stringify()).map_or(
-      // We match `true` by default, because if it's async,
-      // and we didn't treat it as such code will break
-      true,
-      |t| match t {
-        // If the type is VarType::Other, chances are that
-        // We fell thru in the typechecker, so we're gonna
-        // Just going with the default `true`
-        VarType::Other => true,
-        _ => t.is_async(),
-      },
-    );
-
-    if false == is_async {
+    let member = matches!(name, CustomComponent::Member(_));
+    let name = name.stringify();
+    // We match `true` by default, because if it's async,
+    // and we didn't treat it as such code will break
+    let var_type = v.get_variable_type(&name);
+    let is_async = var_type.is_none_or(|t| match t {
+      // If the type is VarType::Other, chances are that
+      // We fell thru in the typechecker, so we're gonna
+      // Just going with the default `true`
+      VarType::Other => true,
+      _ => t.is_async(),
+    });
+
+    let boundary = match (is_async, var_type) {
+      (false, _) => Boundary::Sync,
+      (true, Some(VarType::StreamJSX)) => Boundary::Stream,
+      (true, _) => Boundary::Async,
+    };
+    v.record_render(name, member, boundary);
+
+    if !is_async {
       return Processed::Sync(call_framework_stringify(
         Box::new(transformed),
         v.later_create_ident.clone(),
@@ -82,16 +135,51 @@ pub fn process_transformed_jsx(
 
     let id = generate_random_variable_name(12);
 
-    to_create.push((
-      id.clone(),
-      Expr::Await(AwaitExpr {
-        arg: Box::new(transformed),
-        span: Span::dummy(),
-      }),
-    ));
+    let later = match var_type {
+      Some(VarType::StreamJSX) => Later::Stream(transformed),
+      _ => Later::Await(transformed),
+    };
+
+    to_create.push(AsyncBoundary {
+      id: id.clone(),
+      parent: None,
+      later,
+      fallback: None,
+    });
 
-    return Processed::Async(format!("<div id=\"{id}\"></div>"));
+    let mut div = TplWrapper::new();
+    div.append_quasi("<div id=\"");
+    div.append_expr(boundary_id(v, &id));
+    div.append_quasi("\"></div>");
+
+    Processed::Async(Expr::Tpl(div.build()))
   } else {
-    return Processed::Sync(transformed);
+    Processed::Sync(transformed)
+  }
+}
+
+// Transforms and processes the element, and makes it the parent of
+// every async boundary that ended up in its props, if it's async itself
+pub fn process_jsx_element(
+  v: &TranspileVisitor,
+  jsx_element: Box<JSXElement>,
+  to_create: &mut ToCreateAsync,
+) -> Processed {
+  let first_child = to_create.len();
+
+  let processed = process_transformed_jsx(
+    transpiler::transform(v, jsx_element, to_create),
+    v,
+    to_create,
+  );
+
+  if let Processed::Async(_) = processed {
+    if let Some((boundary, children)) = to_create[first_child..].split_last_mut() {
+      for child in children.iter_mut().filter(|c| c.parent.is_none()) {
+        child.parent = Some(boundary.id.clone());
+      }
+    }
   }
+
+  processed
 }
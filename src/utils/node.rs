@@ -0,0 +1,25 @@
+use std::{fs, process::Command};
+
+// Runs `script` with node once the runtime in `test/impl.js` is set up, next to
+// the `modules` it can import by their file name. Its stdout, `None` if node
+// isn't installed
+pub fn run(script: &str, modules: &[(&str, &str)]) -> Option<String> {
+  let dir = std::env::temp_dir().join(super::generate_random_variable_name(12));
+  fs::create_dir_all(&dir).unwrap();
+  for (name, code) in modules {
+    fs::write(dir.join(name), code).unwrap();
+  }
+  let runtime = format!("import '{}/test/impl.js';\n", env!("CARGO_MANIFEST_DIR"));
+  fs::write(dir.join("run.mjs"), runtime + script).unwrap();
+
+  let output = Command::new("node").arg(dir.join("run.mjs")).output();
+  fs::remove_dir_all(&dir).unwrap();
+
+  let output = output.ok()?;
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  Some(String::from_utf8(output.stdout).unwrap())
+}
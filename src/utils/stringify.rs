@@ -6,13 +6,13 @@ pub trait Stringify {
 
 impl Stringify for Ident {
   fn stringify(self) -> String {
-    return self.sym.to_string();
+    self.sym.to_string()
   }
 }
 
 impl Stringify for Lit {
   fn stringify(self) -> String {
-    return match self {
+    match self {
       Lit::Str(value) => value.value.as_str().to_owned(),
       Lit::Bool(value) => value.value.to_string(),
       Lit::Null(_) => "null".to_owned(),
@@ -20,12 +20,12 @@ impl Stringify for Lit {
       Lit::BigInt(value) => value.value.to_string(),
       Lit::Regex(re) => format!("/{}/{}", re.exp.as_str(), re.flags.as_str()),
       Lit::JSXText(value) => value.value.as_str().to_owned(),
-    };
+    }
   }
 }
 
 pub fn stringify_jsx_object(jsx_object: JSXObject) -> String {
-  return match jsx_object {
+  match jsx_object {
     JSXObject::Ident(ident) => ident.stringify(),
     JSXObject::JSXMemberExpr(member) => {
       format!(
@@ -34,19 +34,19 @@ pub fn stringify_jsx_object(jsx_object: JSXObject) -> String {
         member.prop.stringify()
       )
     }
-  };
+  }
 }
 
 pub fn stringify_jsx_member_expr(jsx_member_expr: JSXMemberExpr) -> String {
-  return format!(
+  format!(
     "{}.{}",
     stringify_jsx_object(jsx_member_expr.obj),
     jsx_member_expr.prop.stringify()
-  );
+  )
 }
 
 pub fn stringify_jsx_element_name(name: JSXElementName) -> String {
-  return match name {
+  match name {
     JSXElementName::Ident(ident) => ident.stringify(),
     JSXElementName::JSXNamespacedName(namespace) => {
       format!(
@@ -56,7 +56,7 @@ pub fn stringify_jsx_element_name(name: JSXElementName) -> String {
       )
     }
     JSXElementName::JSXMemberExpr(member) => stringify_jsx_member_expr(member),
-  };
+  }
 }
 
 pub fn stringify_jsx_attr_name(name: JSXAttrName) -> String {
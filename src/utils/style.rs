@@ -1,175 +1,292 @@
 use crate::{
   tpl_wrapper::TplWrapper,
-  utils::{self, stringify::Stringify},
+  utils::{self, constant::Constant, stringify::Stringify},
 };
 use lazy_static::lazy_static;
-use num_traits::Zero;
 use std::{
-  collections::HashMap,
-  sync::{Arc, Mutex},
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  rc::Rc,
 };
+use swc_common::{util::take::Take, Spanned};
 use swc_ecma_ast::{
-  ComputedPropName, Expr, KeyValueProp, Lit, ObjectLit, Prop, PropName, PropOrSpread, Tpl,
+  ComputedPropName, CondExpr, Expr, KeyValueProp, Lit, ObjectLit, ParenExpr, Prop, PropName,
+  PropOrSpread, Tpl,
 };
 
 pub fn style_object_to_string(obj: ObjectLit) -> Tpl {
   let mut tlp = TplWrapper::new();
 
-  let mut is_first = true;
-  for prop in obj.props {
-    if !is_first {
-      tlp.append_quasi(";");
+  // Skipped at compile time, so they don't need a separator either
+  let entries = obj
+    .props
+    .into_iter()
+    .map(prop_to_entry)
+    .filter(|entry| !matches!(entry, Expr::Lit(Lit::Str(str)) if str.value.is_empty()))
+    .collect::<Vec<_>>();
+
+  // Entries that can still be empty are only separated once they're rendered
+  if entries.len() == 1 || !entries.iter().any(can_be_empty) {
+    for (i, entry) in entries.into_iter().enumerate() {
+      if i > 0 {
+        tlp.append_quasi(";");
+      }
+      tlp.append_expr(entry);
     }
-    is_first = false;
-
-    match prop {
-      PropOrSpread::Prop(prop) => match *prop {
-        Prop::Shorthand(name) => {
-          let str: &str = name.as_ref();
-          tlp.append_quasi(process_style_name(str.to_string()));
-          tlp.append_quasi(": ");
-          tlp.append_expr(utils::call_framework_fn(
-            "___FRAMEWORK_JS_STYLE_VALUE___",
-            vec![
-              Box::new(Expr::Ident(name.clone())).into(),
-              Box::new(Expr::Lit(Lit::Str(name.stringify().into()))).into(),
-            ],
-          ));
-        }
-        Prop::KeyValue(KeyValueProp { key, value }) => {
-          enum StrOrExpr<S: AsRef<str>> {
-            Str(S),
-            Expr(Expr),
-          }
-
-          impl<S: AsRef<str>> StrOrExpr<S> {
-            fn as_expr(&self) -> Expr {
-              match self {
-                StrOrExpr::Str(s) => Expr::Lit(Lit::Str(s.as_ref().into())),
-                StrOrExpr::Expr(e) => e.clone(),
-              }
-            }
-          }
-
-          let key = match key {
-            PropName::Ident(i) => StrOrExpr::Str(i.clone().stringify()),
-            PropName::Str(str) => StrOrExpr::Str(str.value.to_string().clone()),
-            PropName::Computed(ComputedPropName { expr, .. }) => match *expr {
-              Expr::Lit(lit) => match lit {
-                Lit::Str(str) => StrOrExpr::Str(str.value.to_string()),
-                _ => unreachable!(),
-              },
-              Expr::Ident(name) => StrOrExpr::Expr(Expr::Ident(name.clone())),
-              _ => unreachable!(),
-            },
-            _ => unreachable!(),
-          };
-
-          let value = match &*value {
-            Expr::Ident(_) | Expr::Tpl(_) => StrOrExpr::Expr(utils::call_framework_fn(
-              "___FRAMEWORK_JS_STYLE_VALUE___",
-              vec![value.into(), Box::new(key.as_expr()).into()],
-            )),
-            Expr::Lit(lit) => {
-              match &key {
-                StrOrExpr::Str(key) => match lit {
-                  Lit::Str(s) => StrOrExpr::Str(s.value.to_string()),
-                  Lit::JSXText(_) => unimplemented!("JSXText as a value for style?"), // What is this exactly?
-                  Lit::Num(num) => StrOrExpr::Str(if key.starts_with("--") {
-                    num.to_string()
-                  } else {
-                    if num.value == 0.0 {
-                      "0".into()
-                    } else {
-                      if is_unitless_number(&key) {
-                        num.to_string()
-                      } else {
-                        format!("{num}px")
-                      }
-                    }
-                  }),
-                  Lit::BigInt(num) => StrOrExpr::Str(if key.starts_with("--") {
-                    num.value.to_string()
-                  } else {
-                    if num.value.is_zero() {
-                      "0".into()
-                    } else {
-                      if is_unitless_number(&key) {
-                        num.value.to_string()
-                      } else {
-                        format!("{}px", num.value.to_string())
-                      }
-                    }
-                  }),
-                  Lit::Bool(b) => StrOrExpr::Str(b.value.to_string()),
-                  Lit::Null(_) => unreachable!(),
-                  Lit::Regex(_) => unreachable!(),
-                },
-                StrOrExpr::Expr(_) => StrOrExpr::Expr(utils::call_framework_fn(
-                  "___FRAMEWORK_JS_STYLE_VALUE___",
-                  vec![value.into(), Box::new(key.as_expr()).into()],
-                )),
-              }
+  } else {
+    tlp.append_expr(utils::call_framework_fn(
+      "___FRAMEWORK_JS_STYLE_JOIN___",
+      entries
+        .into_iter()
+        .map(|entry| Box::new(entry).into())
+        .collect(),
+    ));
+  }
+
+  tlp.build()
+}
+
+// Every `name: value` declaration in the object, if all of them are known at
+// compile time. Empty entries are left out
+pub fn static_style_declarations(obj: &ObjectLit) -> Option<Vec<String>> {
+  let mut declarations = vec![];
+
+  for prop in obj.props.iter().cloned() {
+    // Reported once the style is converted
+    if let PropOrSpread::Prop(prop) = &prop {
+      if !matches!(**prop, Prop::Shorthand(_) | Prop::KeyValue(_)) {
+        return None;
+      }
+    }
+    let Expr::Lit(Lit::Str(entry)) = prop_to_entry(prop) else {
+      return None;
+    };
+    if !entry.value.is_empty() {
+      declarations.push(entry.value.to_string());
+    }
+  }
+
+  Some(declarations)
+}
+
+// Whether any two `name: value` declarations set the same property, like a
+// shorthand and one of its longhands do
+pub fn has_overlapping_declarations(declarations: &[String]) -> bool {
+  let mut families = HashSet::new();
+  for declaration in declarations {
+    let name = declaration
+      .split_once(':')
+      .map_or("", |(name, _)| name.trim());
+    let Some(family) = property_family(name) else {
+      continue;
+    };
+    if family == "all" || families.contains("all") || !families.insert(family) {
+      return true;
+    }
+  }
+
+  false
+}
+
+// Properties of the same family can override each other, `None` for custom ones
+fn property_family(name: &str) -> Option<&str> {
+  if name.starts_with("--") {
+    return None;
+  }
+
+  // `-webkit-box-shadow` is still a `box-shadow`
+  let name = match name.strip_prefix('-') {
+    Some(prefixed) => prefixed.split_once('-').map_or(prefixed, |(_, name)| name),
+    None => name,
+  };
+  let family = name.split('-').next().unwrap_or(name);
+
+  // The shorthands that aren't a prefix of their longhands
+  Some(match family {
+    "line" if name == "line-height" => "font",
+    "top" | "right" | "bottom" | "left" => "inset",
+    "align" | "justify" | "place" => "place",
+    "row" | "column" | "columns" | "gap" => "gap",
+    _ => family,
+  })
+}
+
+fn prop_to_entry(prop: PropOrSpread) -> Expr {
+  match prop {
+    PropOrSpread::Prop(prop) => match *prop {
+      Prop::Shorthand(name) => style_entry(name.as_ref(), Box::new(Expr::Ident(name.clone()))),
+      Prop::KeyValue(KeyValueProp { key, value }) => {
+        let key = match key {
+          PropName::Ident(i) => i.stringify(),
+          PropName::Str(str) => str.value.to_string(),
+          PropName::Num(num) => num.value.to_string(),
+          PropName::BigInt(num) => num.value.to_string(),
+          PropName::Computed(ComputedPropName { expr, .. }) => match *expr {
+            Expr::Lit(Lit::Str(str)) => str.value.to_string(),
+            expr => {
+              return utils::call_framework_fn(
+                "___FRAMEWORK_JS_STYLE_ENTRY___",
+                vec![value.into(), Box::new(expr).into()],
+              );
             }
-            // As unimplemented expressions pop up, I check if it can be implemented in rust
-            // Or if I need to call it in JS
-            _ => unimplemented!(),
-          };
-
-          match key {
-            StrOrExpr::Str(s) => tlp.append_quasi(process_style_name(s)),
-            StrOrExpr::Expr(expr) => tlp.append_expr(utils::call_framework_fn(
-              "___FRAMEWORK_JS_STYLE_NAME___",
-              vec![Box::new(expr).into()],
-            )),
-          }
-
-          tlp.append_quasi(": ");
-
-          match value {
-            StrOrExpr::Str(s) => tlp.append_quasi(s),
-            StrOrExpr::Expr(expr) => tlp.append_expr(expr),
-          }
-        }
-        _ => unreachable!(
-          "Other `Prop` enumerations should never be present on the `style` component!"
-        ),
-      },
-      PropOrSpread::Spread(spread) => {
-        tlp.append_expr(utils::call_framework_fn(
-          "___FRAMEWORK_JS_STYLE_OBJECT___",
-          vec![spread.expr.into()],
-        ));
+          },
+        };
+
+        style_entry(&key, value)
       }
+      prop => {
+        utils::report_error(
+          prop.span(),
+          "Only plain `name: value` entries can be in a `style` object",
+        );
+        Expr::Lit(Lit::Str("".into()))
+      }
+    },
+    PropOrSpread::Spread(spread) => {
+      utils::call_framework_fn("___FRAMEWORK_JS_STYLE_OBJECT___", vec![spread.expr.into()])
     }
   }
+}
 
-  return tlp.build();
+fn can_be_empty(entry: &Expr) -> bool {
+  match entry {
+    Expr::Lit(Lit::Str(str)) => str.value.is_empty(),
+    Expr::Cond(CondExpr { cons, alt, .. }) => can_be_empty(cons) || can_be_empty(alt),
+    _ => true,
+  }
 }
 
-lazy_static! {
-  static ref STYLE_NAME_CACHE: Arc<Mutex<HashMap<String, &'static str>>> =
-    Arc::new(Mutex::new(HashMap::new()));
+// The whole `style` attribute, objects are converted at compile time,
+// and anything we can't see into is left to the runtime.
+// `None` if the style is known to be empty, like `style={null}`
+pub fn style_to_string(style: Expr) -> Option<Expr> {
+  match convert_style(style) {
+    Expr::Lit(Lit::Str(str)) if str.value.is_empty() => None,
+    style => Some(style),
+  }
 }
 
-fn process_style_name<'a>(name: String) -> &'static str {
-  let map = &mut STYLE_NAME_CACHE.lock().unwrap();
+fn convert_style(style: Expr) -> Expr {
+  if let Some(constant) = Constant::evaluate(&style) {
+    return Expr::Lit(Lit::Str(
+      match constant {
+        Constant::Str(str) => escape_html(str),
+        // Just like `Object.entries` of them, there's nothing to render
+        _ => String::new(),
+      }
+      .into(),
+    ));
+  }
 
-  if let Some(name) = map.get(&name) {
-    return (*name) as &'static str;
+  match style {
+    Expr::Object(obj) => Expr::Tpl(style_object_to_string(obj)),
+    Expr::Paren(ParenExpr { expr, .. }) => convert_style(*expr),
+    Expr::Cond(CondExpr {
+      test, cons, alt, ..
+    }) => Expr::Cond(CondExpr {
+      test,
+      cons: Box::new(convert_style(*cons)),
+      alt: Box::new(convert_style(*alt)),
+      ..CondExpr::dummy()
+    }),
+    style => utils::call_framework_fn(
+      "___FRAMEWORK_JS_STYLE_OBJECT___",
+      vec![Box::new(style).into()],
+    ),
   }
+}
 
-  if name.starts_with("--") {
-    let value = escape_html(name.clone());
-    map.insert(name.clone(), Box::new(value).leak());
+// A single `name: value` style entry, folded into a string literal when it's
+// known at compile time, otherwise processed with `___FRAMEWORK_JS_STYLE_ENTRY___`.
+// Empty if the value is nullish or `false`
+fn style_entry(key: &str, value: Box<Expr>) -> Expr {
+  if let Some(constant) = Constant::evaluate(&value) {
+    let entry = match constant.is_empty_style() {
+      true => String::new(),
+      false => format!(
+        "{}: {}",
+        process_style_name(key),
+        constant.to_style_value(key)
+      ),
+    };
+    return Expr::Lit(Lit::Str(entry.into()));
+  }
 
-    return map.get(&name).unwrap();
+  match *value {
+    Expr::Paren(ParenExpr { expr, .. }) => style_entry(key, expr),
+    // Both branches can still be folded on their own
+    Expr::Cond(CondExpr {
+      test, cons, alt, ..
+    }) => Expr::Cond(CondExpr {
+      test,
+      cons: Box::new(style_entry(key, cons)),
+      alt: Box::new(style_entry(key, alt)),
+      ..CondExpr::dummy()
+    }),
+    value => utils::call_framework_fn(
+      "___FRAMEWORK_JS_STYLE_ENTRY___",
+      vec![
+        Box::new(value).into(),
+        Box::new(Expr::Lit(Lit::Str(key.into()))).into(),
+      ],
+    ),
   }
+}
 
-  let value = escape_html(hyphenate_style_name(&name));
-  map.insert(name.clone(), Box::new(value).leak());
+impl Constant {
+  // Entries React leaves out of the style altogether
+  fn is_empty_style(&self) -> bool {
+    matches!(
+      self,
+      Constant::Null | Constant::Undefined | Constant::Bool(false)
+    )
+  }
 
-  return map.get(&name).unwrap();
+  // Mirrors `___FRAMEWORK_JS_STYLE_VALUE___`
+  fn to_style_value(&self, key: &str) -> String {
+    match self {
+      Constant::Num(num) if !key.starts_with("--") => {
+        if *num != 0.0 && !is_unitless_number(key) {
+          format!("{}px", self.to_js_string())
+        } else {
+          self.to_js_string()
+        }
+      }
+      constant => escape_html(constant.to_js_string().trim().to_owned()),
+    }
+  }
+}
+
+// Past this many names the cache starts over, so computed or unusual keys
+// can't grow it forever in a long-running process
+const STYLE_NAME_CACHE_LIMIT: usize = 4096;
+
+thread_local! {
+  // One per thread, so reads never lock, and parallel builds don't wait on
+  // each other. Plain `Rc`s, not atoms: swc's atom store never frees them
+  static STYLE_NAME_CACHE: RefCell<HashMap<String, Rc<str>>> = RefCell::new(HashMap::new());
+}
+
+fn process_style_name(name: &str) -> Rc<str> {
+  STYLE_NAME_CACHE.with(|cache| {
+    let mut cache = cache.borrow_mut();
+    if let Some(processed) = cache.get(name) {
+      return processed.clone();
+    }
+
+    let processed: Rc<str> = match name.starts_with("--") {
+      true => escape_html(name.to_owned()),
+      false => escape_html(hyphenate_style_name(name)),
+    }
+    .into();
+
+    if cache.len() >= STYLE_NAME_CACHE_LIMIT {
+      cache.clear();
+    }
+    cache.insert(name.to_owned(), processed.clone());
+
+    processed
+  })
 }
 
 /**
@@ -178,12 +295,14 @@ fn process_style_name<'a>(name: String) -> &'static str {
  *
  * Hyphenates a camelcased CSS property name, for example:
  *
- *   > hyphenateStyleName('backgroundColor')
- *   < "background-color"
- *   > hyphenateStyleName('MozTransition')
- *   < "-moz-transition"
- *   > hyphenateStyleName('msTransition')
- *   < "-ms-transition"
+ * ```text
+ * > hyphenateStyleName('backgroundColor')
+ * < "background-color"
+ * > hyphenateStyleName('MozTransition')
+ * < "-moz-transition"
+ * > hyphenateStyleName('msTransition')
+ * < "-ms-transition"
+ * ```
  *
  * As Modernizr suggests (http://modernizr.com/docs/#prefixed), an `ms` prefix
  * is converted to `-ms-`.
@@ -192,7 +311,7 @@ fn hyphenate_style_name<S: AsRef<str>>(name: S) -> String {
   let name = name.as_ref().chars().collect::<Vec<_>>();
   let mut vec = Vec::<char>::with_capacity(name.len() + 10);
 
-  if name.starts_with(&['m', 's']) && name[2].is_ascii_uppercase() {
+  if name.starts_with(&['m', 's']) && name.get(2).is_some_and(|c| c.is_ascii_uppercase()) {
     vec.push('-');
   }
 
@@ -205,10 +324,10 @@ fn hyphenate_style_name<S: AsRef<str>>(name: S) -> String {
     }
   }
 
-  return vec.into_iter().collect();
+  vec.into_iter().collect()
 }
 
-fn escape_html(value: String) -> String {
+pub fn escape_html(value: String) -> String {
   let mut vec = Vec::<char>::with_capacity(value.len() + 20);
 
   for c in value.chars() {
@@ -222,85 +341,19 @@ fn escape_html(value: String) -> String {
     }
   }
 
-  return vec.into_iter().collect();
+  vec.into_iter().collect()
 }
 
-const UNITLESS_NUMBERS: &'static [&'static str] = &[
-  "animationIterationCount",
-  "aspectRatio",
-  "borderImageOutset",
-  "borderImageSlice",
-  "borderImageWidth",
-  "boxFlex",
-  "boxFlexGroup",
-  "boxOrdinalGroup",
-  "columnCount",
-  "columns",
-  "flex",
-  "flexGrow",
-  "flexPositive",
-  "flexShrink",
-  "flexNegative",
-  "flexOrder",
-  "gridArea",
-  "gridRow",
-  "gridRowEnd",
-  "gridRowSpan",
-  "gridRowStart",
-  "gridColumn",
-  "gridColumnEnd",
-  "gridColumnSpan",
-  "gridColumnStart",
-  "fontWeight",
-  "lineClamp",
-  "lineHeight",
-  "opacity",
-  "order",
-  "orphans",
-  "scale",
-  "tabSize",
-  "widows",
-  "zIndex",
-  "zoom",
-  "fillOpacity", // SVG-related properties
-  "floodOpacity",
-  "stopOpacity",
-  "strokeDasharray",
-  "strokeDashoffset",
-  "strokeMiterlimit",
-  "strokeOpacity",
-  "strokeWidth",
-  "MozAnimationIterationCount", // Known Prefixed Properties
-  "MozBoxFlex",                 // TODO: Remove these since they shouldn't be used in modern code
-  "MozBoxFlexGroup",
-  "MozLineClamp",
-  "msAnimationIterationCount",
-  "msFlex",
-  "msZoom",
-  "msFlexGrow",
-  "msFlexNegative",
-  "msFlexOrder",
-  "msFlexPositive",
-  "msFlexShrink",
-  "msGridColumn",
-  "msGridColumnSpan",
-  "msGridRow",
-  "msGridRowSpan",
-  "WebkitAnimationIterationCount",
-  "WebkitBoxFlex",
-  "WebKitBoxFlexGroup",
-  "WebkitBoxOrdinalGroup",
-  "WebkitColumnCount",
-  "WebkitColumns",
-  "WebkitFlex",
-  "WebkitFlexGrow",
-  "WebkitFlexPositive",
-  "WebkitFlexShrink",
-  "WebkitLineClamp",
-];
+lazy_static! {
+  // Shared with the runtime in `test/impl.js`, so the two can't drift apart
+  static ref UNITLESS_NUMBERS: HashSet<&'static str> = serde_json::from_str(include_str!(
+    "../../data/unitless-numbers.json"
+  ))
+  .expect("`data/unitless-numbers.json` should be a list of style names");
+}
 
 fn is_unitless_number(name: &str) -> bool {
-  return UNITLESS_NUMBERS.iter().any(|a| a.eq(&name));
+  UNITLESS_NUMBERS.contains(name)
 }
 
 #[cfg(test)]
@@ -350,31 +403,46 @@ mod test {
   #[test]
   fn process_style_name() {
     assert_eq!(
-      super::process_style_name("backgroundColor".into()),
+      &*super::process_style_name("backgroundColor"),
       "background-color"
     );
 
     assert_eq!(
-      super::process_style_name("MozTransition".into()),
+      &*super::process_style_name("MozTransition"),
       "-moz-transition"
     );
 
     assert_eq!(
-      super::process_style_name("msTransition".into()),
+      &*super::process_style_name("msTransition"),
       "-ms-transition"
     );
 
     assert_eq!(
-      super::process_style_name("--custom-css-var".into()),
+      &*super::process_style_name("--custom-css-var"),
       "--custom-css-var"
     );
 
     assert_eq!(
-      super::process_style_name("--cust'om-css-var".into()),
+      &*super::process_style_name("--cust'om-css-var"),
       "--cust&#x27;om-css-var"
     );
   }
 
+  #[test]
+  fn style_names_are_freed_once_the_cache_starts_over() {
+    let first = std::rc::Rc::downgrade(&super::process_style_name("--first"));
+    assert!(first.upgrade().is_some());
+
+    for i in 0..super::STYLE_NAME_CACHE_LIMIT {
+      assert_eq!(
+        &*super::process_style_name(&format!("--var{i}")),
+        format!("--var{i}")
+      );
+    }
+    // Nothing else holds on to it, so it's gone
+    assert!(first.upgrade().is_none());
+  }
+
   fn convert_src<F: Fn(swc_ecma_ast::Program) -> swc_ecma_ast::Program>(
     f: F,
   ) -> impl Fn(String) -> String {
@@ -388,7 +456,7 @@ mod test {
     use swc_ecma_ast::EsVersion;
     use swc_ecma_parser::{Syntax, TsConfig};
 
-    return move |src| {
+    move |src| {
       let cm = Arc::<SourceMap>::default();
 
       let c = swc::Compiler::new(cm.clone());
@@ -435,8 +503,8 @@ mod test {
         })
         .unwrap();
 
-      return code.code;
-    };
+      code.code
+    }
   }
 
   #[test]
@@ -490,14 +558,137 @@ mod test {
 
     assert_eq!(
       convert("({backgroundColor: '#121212', ...styles})".into()),
-      "`background-color: #121212;${global.___FRAMEWORK_JS_STYLE_OBJECT___(styles)}`;"
+      "`${global.___FRAMEWORK_JS_STYLE_JOIN___(\"background-color: #121212\",global.___FRAMEWORK_JS_STYLE_OBJECT___(styles))}`;"
     );
 
     assert_eq!(
       convert("({backgroundColor})".into()),
-      "`background-color: ${global.___FRAMEWORK_JS_STYLE_VALUE___(backgroundColor,\"backgroundColor\")}`;"
+      "`${global.___FRAMEWORK_JS_STYLE_ENTRY___(backgroundColor,\"backgroundColor\")}`;"
     );
 
     assert_eq!(convert("({flex: 1})".into()), "`flex: 1`;");
+
+    assert_eq!(
+      convert("({width: 10 + 'em', height: -4, margin: `${2 * 4}px auto`})".into()),
+      "`width: 10em;height: -4px;margin: 8px auto`;"
+    );
+
+    assert_eq!(
+      convert("({color: dark ? 'white' : theme.color})".into()),
+      "`${dark?\"color: white\":global.___FRAMEWORK_JS_STYLE_ENTRY___(theme.color,\"color\")}`;"
+    );
+
+    assert_eq!(
+      convert("({width: size + 'px'})".into()),
+      "`${global.___FRAMEWORK_JS_STYLE_ENTRY___(size+\"px\",\"width\")}`;"
+    );
+
+    assert_eq!(
+      convert("({[name]: getValue()})".into()),
+      "`${global.___FRAMEWORK_JS_STYLE_ENTRY___(getValue(),name)}`;"
+    );
+
+    assert_eq!(
+      convert("({color: null, margin: 0, width: undefined, display: false})".into()),
+      "`margin: 0`;"
+    );
+
+    assert_eq!(
+      convert("({color: dark ? 'white' : null})".into()),
+      "`${dark?\"color: white\":\"\"}`;"
+    );
+  }
+
+  #[test]
+  fn hyphenate_short_names() {
+    assert_eq!(super::hyphenate_style_name(""), "");
+    assert_eq!(super::hyphenate_style_name("m"), "m");
+    assert_eq!(super::hyphenate_style_name("ms"), "ms");
+    assert_eq!(super::hyphenate_style_name("msA"), "-ms-a");
+  }
+
+  // Every `name: value` entry, the way the runtime in `test/impl.js` renders them
+  fn js_style_entries(entries: &[(String, f64)]) -> Vec<String> {
+    let output = crate::utils::node::run(
+      &format!(
+        "const entries = {};
+         console.log(JSON.stringify(entries.map(([name, value]) =>
+           `${{global.___FRAMEWORK_JS_STYLE_NAME___(name)}}: ${{global.___FRAMEWORK_JS_STYLE_VALUE___(value, name)}}`
+         )));",
+        serde_json::to_string(entries).unwrap(),
+      ),
+      &[],
+    )
+    .expect("failed to run node");
+    serde_json::from_str(&output).unwrap()
+  }
+
+  fn style_name() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+
+    let unitless = super::UNITLESS_NUMBERS
+      .iter()
+      .map(|name| name.to_string())
+      .collect::<Vec<_>>();
+
+    prop_oneof![
+      proptest::sample::select(unitless),
+      "[a-zA-Z]{0,12}",
+      "ms[A-Z]?[a-z]{0,6}",
+      "--[a-zA-Z-]{1,8}",
+    ]
+  }
+
+  fn style_number() -> impl proptest::strategy::Strategy<Value = f64> {
+    use proptest::prelude::*;
+
+    prop_oneof![
+      Just(0.0),
+      Just(-0.0),
+      (-10_000i32..10_000).prop_map(f64::from),
+      any::<f64>().prop_filter("JSON has no NaN or Infinity", |num| num.is_finite()),
+      // Where JS switches to and from exponents
+      (-30i32..30, -9i32..10).prop_map(|(exponent, digit)| f64::from(digit) * 10f64.powi(exponent)),
+    ]
+  }
+
+  #[test]
+  fn rust_and_js_styles_agree() {
+    use crate::utils::constant::Constant;
+    use proptest::test_runner::{Config, FileFailurePersistence, TestRunner};
+    use std::process::Command;
+
+    // Without node there's nothing to compare with, so no case runs at all
+    if Command::new("node").arg("--version").output().is_err() {
+      eprintln!("skipped rust_and_js_styles_agree, node isn't installed");
+      return;
+    }
+
+    let entries = proptest::collection::vec((style_name(), style_number()), 1..64);
+    let config = Config {
+      cases: 16,
+      failure_persistence: Some(Box::new(FileFailurePersistence::Direct(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/proptest-regressions/utils/style.txt"
+      )))),
+      ..Config::default()
+    };
+    TestRunner::new(config)
+      .run(&entries, |entries| {
+        let rust = entries
+          .iter()
+          .map(|(name, value)| {
+            format!(
+              "{}: {}",
+              &*super::process_style_name(name),
+              Constant::Num(*value).to_style_value(name)
+            )
+          })
+          .collect::<Vec<_>>();
+
+        proptest::prop_assert_eq!(rust, js_style_entries(&entries));
+        Ok(())
+      })
+      .unwrap();
   }
 }
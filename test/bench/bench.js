@@ -0,0 +1,44 @@
+// Renders the page compiled with and without hoisting, and compares them
+// Built and run by `bench.sh` in the repo root
+import '../impl.js';
+import { performance } from 'perf_hooks';
+
+const RENDERS = 2000;
+const controller = { enqueue() {} };
+
+async function bench(name) {
+  const { default: Page } = await import(`./${name}.js`);
+
+  // Warm up, so both variants are measured with the JIT settled
+  for (let i = 0; i < RENDERS / 10; i++) {
+    const [, later] = await Page();
+    await later(controller);
+  }
+
+  global.gc?.();
+  const heap = process.memoryUsage().heapUsed;
+  const start = performance.now();
+  for (let i = 0; i < RENDERS; i++) {
+    const [, later] = await Page();
+    await later(controller);
+  }
+  const time = performance.now() - start;
+  const allocated = process.memoryUsage().heapUsed - heap;
+
+  const [html] = await Page();
+  return { name, time, allocated, html };
+}
+
+const inline = await bench('inline');
+const hoisted = await bench('hoisted');
+
+if (inline.html !== hoisted.html) {
+  throw new Error('Hoisting changed the rendered html!');
+}
+
+for (const { name, time, allocated } of [inline, hoisted]) {
+  console.log(
+    `${name.padEnd(8)} ${(time / RENDERS).toFixed(3)}ms/render, ` +
+      `${(allocated / 1024).toFixed(0)}KiB heap growth over ${RENDERS} renders`,
+  );
+}
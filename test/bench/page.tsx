@@ -0,0 +1,44 @@
+function Item({ index }) {
+  return (
+    <li class="item">
+      <span class="item-label">Item number</span>
+      <b>{index}</b>
+      <p class="item-description">
+        A longer piece of static markup, the kind every list item in a real page carries around.
+      </p>
+    </li>
+  );
+}
+
+export default function Page() {
+  const items = [];
+  for (let i = 0; i < 200; i++) {
+    items.push(<Item index={i} />);
+  }
+
+  return (
+    <html>
+      <head>
+        <title>Benchmark</title>
+        <meta charset="utf-8" />
+        <meta name="viewport" content="width=device-width, initial-scale=1" />
+      </head>
+      <body>
+        <header class="header">
+          <nav class="navigation">
+            <a href="/">Home</a>
+            <a href="/about">About</a>
+            <a href="/blog">Blog</a>
+            <a href="/contact">Contact</a>
+          </nav>
+        </header>
+        <main>
+          <ul class="items">{items}</ul>
+        </main>
+        <footer class="footer">
+          <p>Nothing here changes between renders, so none of it has to be rebuilt.</p>
+        </footer>
+      </body>
+    </html>
+  );
+}
@@ -1,3 +1,7 @@
+import { createRequire } from 'module';
+
+const require = createRequire(import.meta.url);
+
 /**
  * 
  * @param {unknown} item The item to stringify
@@ -36,6 +40,214 @@ global.___FRAMEWORK_JS_STRINGIFY___ = (item, toCreate) => {
   }
 }
 
+let nextBoundaryId = 0;
+
+/**
+ * Every rendered JSX expression that has async boundaries gets a new prefix
+ * for their ids, so a boundary rendered in a loop still gets a unique id
+ *
+ * @returns {string} Prefix for the ids of the async boundaries
+ */
+global.___FRAMEWORK_JS_ID___ = () => `_${(nextBoundaryId++).toString(36)}`;
+
+/**
+ * `Promise.allSettled`, which runtimes older than ES2020 don't have
+ *
+ * @param {Array<unknown>} promises The promises to wait for
+ * @returns {Promise<PromiseSettledResult<unknown>[]>} Resolves once all of them settled
+ */
+global.___FRAMEWORK_JS_ALL_SETTLED___ = (promises) => Promise.all(
+  promises.map((promise) => Promise.resolve(promise).then(
+    (value) => ({ status: 'fulfilled', value }),
+    (reason) => ({ status: 'rejected', reason })
+  ))
+);
+
+/**
+ * `for await`, which runtimes older than ES2018 don't have, over the chunks
+ * of an `async function*` component. Like it, the generator is closed when
+ * `callback` throws.
+ *
+ * @param {AsyncIterable<unknown>} chunks The async generator of the component
+ * @param {(chunk: unknown) => unknown} callback Called with every chunk, in order, awaited
+ * @returns {Promise<void>} Resolves after the last chunk
+ */
+async function forEachChunk(chunks, callback) {
+  const iterator = typeof chunks.next === 'function' ? chunks : chunks[Symbol.asyncIterator]();
+  for (;;) {
+    const result = await iterator.next();
+    if (result.done) {
+      return;
+    }
+
+    try {
+      await callback(result.value);
+    } catch (error) {
+      if (typeof iterator.return === 'function') {
+        await iterator.return();
+      }
+      throw error;
+    }
+  }
+}
+
+/**
+ * Makes a string safe to embed as a JS string literal inside a `<script>`
+ *
+ * @param {string} html The html to embed
+ * @returns {string} A JS string literal
+ */
+function toScriptString(html) {
+  return JSON.stringify(html).replace(/</g, '\\u003c');
+}
+
+/**
+ * Enqueues a `<script>` that runs `code`, and removes itself afterwards
+ *
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {string} code The code to run
+ */
+function enqueueScript(controller, code) {
+  const scriptId = `_${Math.random().toString(36).slice(2)}`;
+  controller.enqueue(
+    `<script id="${scriptId}">${code}document.getElementById("${scriptId}").remove();</script>`
+  );
+}
+
+/**
+ * Called with every error an async component rejects with.
+ * Reassign it to report them somewhere else.
+ *
+ * @param {unknown} error What the component rejected with
+ * @param {{ id: string }} info Id of the placeholder of the component
+ */
+global.___FRAMEWORK_JS_ON_ERROR___ = (error, info) => {
+  console.error(`Async component "${info.id}" failed:`, error);
+}
+
+/**
+ * Reports the error, and streams the fallback of the closest `<ErrorBoundary>`,
+ * or nothing if there isn't one, in place of the placeholder.
+ *
+ * @param {string} id Id of the placeholder element
+ * @param {unknown} error What the component rejected with
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {Promise<unknown> | undefined} parentPlaced Resolves once the parent boundary is placed
+ * @param {(() => unknown) | undefined} fallback Renders the fallback
+ * @returns {Promise<() => Promise<PromiseSettledResult<unknown>[]>>} Resolves once the fallback is placed
+ */
+async function placeFallback(id, error, controller, parentPlaced, fallback) {
+  global.___FRAMEWORK_JS_ON_ERROR___(error, { id });
+
+  const toCreate = [];
+  let html = fallback ? '' + global.___FRAMEWORK_JS_STRINGIFY___(fallback(), toCreate) : '';
+  html = newStyles(controller, html) + html;
+
+  await parentPlaced;
+  enqueueScript(
+    controller,
+    `{const e=document.getElementById("${id}");if(e)e.outerHTML=${toScriptString(html)};}`
+  );
+
+  return () => global.___FRAMEWORK_JS_ALL_SETTLED___(toCreate.map((create) => create(controller)));
+}
+
+/**
+ * Streams an async component in place of its `<div id>` placeholder.
+ *
+ * Every async boundary is started right away, but its html is only placed
+ * once its parent's is, since that's where its placeholder lives.
+ * The boundaries it renders are only created after it's placed.
+ *
+ * @param {string} id Id of the placeholder element
+ * @param {(toCreate: Array<(controller: ReadableStreamDefaultController) => Promise<unknown>>) => unknown} render Renders the component, collecting the `later` functions of its props into `toCreate`
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {Promise<unknown>} [parentPlaced] Resolves once the parent boundary is placed
+ * @param {() => unknown} [fallback] Renders the fallback of the closest `<ErrorBoundary>`
+ * @returns {Promise<() => Promise<PromiseSettledResult<unknown>[]>>} Resolves once placed, to a function that renders the rest of the subtree
+ */
+global.___FRAMEWORK_JS_ASYNC___ = async (id, render, controller, parentPlaced, fallback) => {
+  const toCreate = [];
+  let html;
+  try {
+    html = '' + global.___FRAMEWORK_JS_STRINGIFY___(await render(toCreate), toCreate);
+  } catch (error) {
+    return placeFallback(id, error, controller, parentPlaced, fallback);
+  }
+  html = newStyles(controller, html) + html;
+
+  // Rejects if the parent failed, and then there's nowhere to place us
+  await parentPlaced;
+  enqueueScript(
+    controller,
+    `{const e=document.getElementById("${id}");if(e)e.outerHTML=${toScriptString(html)};}`
+  );
+
+  return () => global.___FRAMEWORK_JS_ALL_SETTLED___(toCreate.map((create) => create(controller)));
+}
+
+/**
+ * Streams every chunk an `async function*` component yields,
+ * in order, in front of its placeholder, as soon as it's produced.
+ * The placeholder is removed after the last chunk.
+ *
+ * @param {string} id Id of the placeholder element
+ * @param {(toCreate: Array<(controller: ReadableStreamDefaultController) => Promise<unknown>>) => AsyncIterable<unknown>} render Creates the async generator of the component
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {Promise<unknown>} [parentPlaced] Resolves once the parent boundary is placed
+ * @param {() => unknown} [fallback] Renders the fallback of the closest `<ErrorBoundary>`
+ * @returns {Promise<() => Promise<PromiseSettledResult<unknown>[]>>} Resolves once every chunk is placed, to a function that waits for the rest of the subtree
+ */
+global.___FRAMEWORK_JS_STREAM___ = async (id, render, controller, parentPlaced, fallback) => {
+  const toCreate = [];
+  const later = [];
+
+  try {
+    await forEachChunk(render(toCreate), async (chunk) => {
+      /** @type {Array<(controller: ReadableStreamDefaultController) => Promise<unknown>>} */
+      const chunkToCreate = [];
+      let html = '' + global.___FRAMEWORK_JS_STRINGIFY___(chunk, chunkToCreate);
+      html = newStyles(controller, html) + html;
+
+      await parentPlaced;
+      enqueueScript(
+        controller,
+        `{const e=document.getElementById("${id}");if(e)e.insertAdjacentHTML("beforebegin",${toScriptString(html)});}`
+      );
+
+      later.push(...chunkToCreate.map((create) => create(controller)));
+    });
+  } catch (error) {
+    // The chunks that made it stay, the fallback takes the placeholder's place
+    const rest = await placeFallback(id, error, controller, parentPlaced, fallback);
+    return () => global.___FRAMEWORK_JS_ALL_SETTLED___([...later, rest()]);
+  }
+
+  await parentPlaced;
+  enqueueScript(controller, `{const e=document.getElementById("${id}");if(e)e.remove();}`);
+
+  return () => global.___FRAMEWORK_JS_ALL_SETTLED___([
+    ...later,
+    ...toCreate.map((create) => create(controller)),
+  ]);
+}
+
+/**
+ * Renders every chunk of an `async function*` component into one string,
+ * for when streaming is buffered.
+ *
+ * @param {AsyncIterable<unknown>} chunks The async generator of the component
+ * @param {Array<(controller: ReadableStreamDefaultController) => Promise<unknown>>} toCreate Collects the `later` functions of the chunks
+ * @returns {Promise<string>} The html of all of the chunks
+ */
+global.___FRAMEWORK_JS_COLLECT___ = async (chunks, toCreate) => {
+  let html = '';
+  await forEachChunk(chunks, (chunk) => {
+    html += global.___FRAMEWORK_JS_STRINGIFY___(chunk, toCreate);
+  });
+  return html;
+}
+
 const CAPITAL_A = 'A'.charCodeAt(0);
 const CAPITAL_Z = 'Z'.charCodeAt(0);
 
@@ -171,79 +383,8 @@ global.___FRAMEWORK_JS_STYLE_NAME___ = (name) => {
   return result;
 }
 
-const unitlessNumbers = new Set([
-  'animationIterationCount',
-  'aspectRatio',
-  'borderImageOutset',
-  'borderImageSlice',
-  'borderImageWidth',
-  'boxFlex',
-  'boxFlexGroup',
-  'boxOrdinalGroup',
-  'columnCount',
-  'columns',
-  'flex',
-  'flexGrow',
-  'flexPositive',
-  'flexShrink',
-  'flexNegative',
-  'flexOrder',
-  'gridArea',
-  'gridRow',
-  'gridRowEnd',
-  'gridRowSpan',
-  'gridRowStart',
-  'gridColumn',
-  'gridColumnEnd',
-  'gridColumnSpan',
-  'gridColumnStart',
-  'fontWeight',
-  'lineClamp',
-  'lineHeight',
-  'opacity',
-  'order',
-  'orphans',
-  'scale',
-  'tabSize',
-  'widows',
-  'zIndex',
-  'zoom',
-  'fillOpacity', // SVG-related properties
-  'floodOpacity',
-  'stopOpacity',
-  'strokeDasharray',
-  'strokeDashoffset',
-  'strokeMiterlimit',
-  'strokeOpacity',
-  'strokeWidth',
-  'MozAnimationIterationCount', // Known Prefixed Properties
-  'MozBoxFlex', // TODO: Remove these since they shouldn't be used in modern code
-  'MozBoxFlexGroup',
-  'MozLineClamp',
-  'msAnimationIterationCount',
-  'msFlex',
-  'msZoom',
-  'msFlexGrow',
-  'msFlexNegative',
-  'msFlexOrder',
-  'msFlexPositive',
-  'msFlexShrink',
-  'msGridColumn',
-  'msGridColumnSpan',
-  'msGridRow',
-  'msGridRowSpan',
-  'WebkitAnimationIterationCount',
-  'WebkitBoxFlex',
-  'WebKitBoxFlexGroup',
-  'WebkitBoxOrdinalGroup',
-  'WebkitColumnCount',
-  'WebkitColumns',
-  'WebkitFlex',
-  'WebkitFlexGrow',
-  'WebkitFlexPositive',
-  'WebkitFlexShrink',
-  'WebkitLineClamp',
-]);
+// Shared with the compiler, so the two can't drift apart
+const unitlessNumbers = new Set(require('../data/unitless-numbers.json'));
 
 /**
  * 
@@ -263,13 +404,178 @@ global.___FRAMEWORK_JS_STYLE_VALUE___ = (styleValue, styleName) => {
   }
 }
 
+/**
+ * React leaves these out of the style altogether
+ * @param {unknown} styleValue
+ * @returns {boolean}
+ */
+function isEmptyStyleValue(styleValue) {
+  return styleValue === null || styleValue === undefined || styleValue === false;
+}
+
 /**
  * 
- * @param {object} style Style object to process
+ * @param {unknown} styleValue Style value to process
+ * @param {string} styleName Name of the style
+ * @returns {string} The `name: value` entry, or an empty string if the value is empty
+ */
+global.___FRAMEWORK_JS_STYLE_ENTRY___ = (styleValue, styleName) => {
+  if (isEmptyStyleValue(styleValue)) {
+    return '';
+  }
+  return `${global.___FRAMEWORK_JS_STYLE_NAME___(styleName)}: ${global.___FRAMEWORK_JS_STYLE_VALUE___(styleValue, styleName)}`;
+}
+
+/**
+ * 
+ * @param {object | string | null | undefined | false} style Style object to process
  * @returns {string} Processed style object
  */
 global.___FRAMEWORK_JS_STYLE_OBJECT___ = (style) => {
-  return Object.entries(style).map(([key, value]) =>
-    `${global.___FRAMEWORK_JS_STYLE_NAME___(key)}: ${global.___FRAMEWORK_JS_STYLE_VALUE___(value, key)}`
-  ).join(';');
+  if (isEmptyStyleValue(style)) {
+    return '';
+  }
+  if (typeof style === 'string') {
+    return escapeTextForBrowser(style);
+  }
+  return Object.entries(style)
+    .filter(([, value]) => !isEmptyStyleValue(value))
+    .map(([key, value]) => global.___FRAMEWORK_JS_STYLE_ENTRY___(value, key))
+    .join(';');
+}
+
+/**
+ * 
+ * @param {...string} entries `name: value` entries, the ones only known at runtime can be empty
+ * @returns {string} The non-empty entries, separated by `;`
+ */
+global.___FRAMEWORK_JS_STYLE_JOIN___ = (...entries) => {
+  return entries.filter((entry) => entry !== '').join(';');
+}
+
+/**
+ * Rules extracted from `css` templates and atomic styles, registered once per
+ * module. Class name => its place in the stylesheet, and its rule
+ * @type Map<string, [number, string]>
+ */
+const stylesheet = new Map();
+
+/**
+ * Where the `<style>` of a page goes, replaced once all of its html is known
+ */
+const STYLESHEET_MARKER = '<!--framework-js-stylesheet-->';
+
+/**
+ * Every class name the compiler generates
+ */
+const CLASS_NAME = /\b_[0-9a-f]{16}\b/g;
+
+/**
+ * Class names of the rules already sent in a response
+ * @type WeakMap<object, Set<string>>
+ */
+const sentRules = new WeakMap();
+
+/**
+ * 
+ * @param {Record<string, string>} rules The module's rules, by class name
+ */
+global.___FRAMEWORK_JS_CSS___ = (rules) => {
+  for (const className of Object.keys(rules)) {
+    if (!stylesheet.has(className)) {
+      stylesheet.set(className, [stylesheet.size, rules[className]]);
+    }
+  }
+}
+
+/**
+ * The registered class names `html` uses, in stylesheet order
+ *
+ * @param {string} html The rendered html
+ * @param {Set<string>} [skip] Class names to leave out
+ * @returns {string[]} The class names
+ */
+function usedClassNames(html, skip) {
+  const used = new Set();
+  for (const className of html.match(CLASS_NAME) || []) {
+    if (stylesheet.has(className) && !(skip && skip.has(className))) {
+      used.add(className);
+    }
+  }
+  return [...used].sort((a, b) => stylesheet.get(a)[0] - stylesheet.get(b)[0]);
+}
+
+/**
+ * 
+ * @param {string[]} classNames The class names of the rules
+ * @returns {string} A `<style>` with their rules, or nothing without any
+ */
+function styleOf(classNames) {
+  if (classNames.length === 0) {
+    return '';
+  }
+  return `<style>${classNames.map((className) => stylesheet.get(className)[1]).join('')}</style>`;
+}
+
+/**
+ * The rules `html` needs that weren't sent in this response yet
+ *
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {string} html Html about to be streamed
+ * @returns {string} A `<style>` with them, or nothing
+ */
+function newStyles(controller, html) {
+  let sent = sentRules.get(controller);
+  if (!sent) {
+    sent = new Set();
+    sentRules.set(controller, sent);
+  }
+
+  const classNames = usedClassNames(html, sent);
+  classNames.forEach((className) => sent.add(className));
+  return styleOf(classNames);
+}
+
+/**
+ * Puts the rules the page's html uses in its `<head>`
+ *
+ * @param {string} html The html of the element the `<head>` is in
+ * @returns {string} The html, with a `<style>` only if it needs one
+ */
+global.___FRAMEWORK_JS_STYLESHEET___ = (html) => {
+  if (html.indexOf(STYLESHEET_MARKER) === -1) {
+    return html;
+  }
+  return html.replace(STYLESHEET_MARKER, () => styleOf(usedClassNames(html)));
+}
+
+/**
+ * Marks the rules in the `<head>` as sent, so streamed html doesn't repeat them
+ *
+ * @param {ReadableStreamDefaultController} controller The controller of the response
+ * @param {string} html The html with the `<head>`
+ */
+global.___FRAMEWORK_JS_STYLESHEET_SENT___ = (controller, html) => {
+  newStyles(controller, html);
+}
+
+/**
+ * `clsx` style class names, falsy entries are left out
+ * @param {unknown} value A string, number, array or object of class names
+ * @returns {string} Escaped class names
+ */
+global.___FRAMEWORK_JS_CLASS___ = (value) => {
+  if (typeof value === 'string' || typeof value === 'number') {
+    return value ? escapeTextForBrowser('' + value) : '';
+  }
+  if (Array.isArray(value)) {
+    return value.map(global.___FRAMEWORK_JS_CLASS___).filter(Boolean).join(' ');
+  }
+  if (value && typeof value === 'object') {
+    return Object.keys(value)
+      .filter((key) => value[key])
+      .map((key) => escapeTextForBrowser(key))
+      .join(' ');
+  }
+  return '';
 }